@@ -0,0 +1,79 @@
+use crate::logging::Level;
+use anyhow::{anyhow, Result};
+
+/// Command line arguments: a command with its positional arguments, plus flags.
+#[derive(Default)]
+pub(crate) struct Args {
+    /// The command (e.g. "diff") followed by its arguments. Empty to run the full pipeline.
+    pub(crate) positional: Vec<String>,
+    /// Tag the commit of each flashed build.
+    pub(crate) tag: bool,
+    /// Replace generated files even if they were edited by hand.
+    pub(crate) force: bool,
+    /// Path of a specific .zip file, extracted Oryx source folder or keymap.c to run the pipeline on, instead of
+    /// the most recent download.
+    pub(crate) input: Option<String>,
+    /// Oryx layout id, optionally followed by "/<revision id>", of the download to run the pipeline on.
+    pub(crate) revision: Option<String>,
+    /// Oryx layout id, optionally followed by "/<revision id>", to download from Oryx and run the pipeline on.
+    pub(crate) fetch: Option<String>,
+    /// Choose the download to run the pipeline on from a list of recent ones.
+    pub(crate) pick: bool,
+    /// How much to print: -q for warnings only, -v for rover's decisions and stage timings, -vv for everything.
+    pub(crate) verbosity: Level,
+    /// Path of a file to log every message and stage to, one JSON object per line.
+    pub(crate) log_json: Option<String>,
+    /// Path of a file to write a JSON summary of the run to.
+    pub(crate) report: Option<String>,
+}
+
+impl Args {
+    pub(crate) fn parse() -> Result<Self> {
+        let mut args = Self::default();
+        let mut arguments = std::env::args().skip(1);
+        while let Some(arg) = arguments.next() {
+            let mut value = || {
+                arguments
+                    .next()
+                    .ok_or_else(|| anyhow!("Option '{arg}' needs a value."))
+            };
+            match arg.as_str() {
+                "--tag" => args.tag = true,
+                "--force" => args.force = true,
+                "--input" => args.input = Some(value()?),
+                "--revision" => args.revision = Some(value()?),
+                "--fetch" => args.fetch = Some(value()?),
+                "--pick" => args.pick = true,
+                "-q" | "--quiet" => args.verbosity = Level::Warn,
+                "-v" | "--verbose" => args.verbosity = args.verbosity.louder(),
+                "-vv" => args.verbosity = args.verbosity.louder().louder(),
+                "--log-json" => args.log_json = Some(value()?),
+                "--report" => args.report = Some(value()?),
+                _ if arg.starts_with('-') => return Err(anyhow!("Unknown option '{arg}'.")),
+                _ => args.positional.push(arg),
+            }
+        }
+        args.check_input_choice()?;
+        Ok(args)
+    }
+
+    /// --input, --fetch, --revision and --pick each choose the export to use, so at most one may be given.
+    fn check_input_choice(&self) -> Result<()> {
+        let chosen = [
+            ("--input", self.input.is_some()),
+            ("--fetch", self.fetch.is_some()),
+            ("--revision", self.revision.is_some()),
+            ("--pick", self.pick),
+        ]
+        .into_iter()
+        .filter_map(|(option, given)| given.then_some(option))
+        .collect::<Vec<_>>();
+        if chosen.len() > 1 {
+            return Err(anyhow!(
+                "Options {} each choose the export to use; give only one.",
+                chosen.join(", ")
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,12 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::{fs, path::Path};
+
+/// Lowercase hex SHA-256 of the bytes.
+pub(crate) fn sha256(contents: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(contents))
+}
+
+pub(crate) fn sha256_file(path: &Path) -> Result<String> {
+    Ok(sha256(&fs::read(path)?))
+}
@@ -1,13 +1,214 @@
+use crate::{
+    config::{self, Config},
+    logging::{self, Level},
+    time_stamp,
+};
 use anyhow::{anyhow, Result};
-use std::process::Command;
+use std::{
+    error, fmt, fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{mpsc, Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
 
-pub(crate) fn run(title: &str, command: &mut Command) -> Result<()> {
-    print!("{title}...");
-    if command.output()?.status.success() {
-        println!("done.");
-        Ok(())
+// How many trailing lines of stderr to include in the error when a command fails.
+const STDERR_TAIL_LINES: usize = 20;
+
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// Everything a command printed, in the order it printed it.
+#[derive(Debug)]
+pub(crate) struct Output {
+    pub(crate) stdout: Vec<String>,
+    pub(crate) stderr: Vec<String>,
+}
+
+/// The error returned when a command runs but exits unsuccessfully, with everything it printed.
+#[derive(Debug)]
+pub(crate) struct Failure {
+    pub(crate) command: String,
+    pub(crate) exit_code: Option<i32>,
+    pub(crate) output: Output,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let stderr = &self.output.stderr;
+        let tail = &stderr[stderr.len().saturating_sub(STDERR_TAIL_LINES)..];
+        write!(
+            f,
+            "Command failed with exit code {}: {}\n{}\n(Full output in '{}'.)",
+            self.exit_code
+                .map_or("none".to_string(), |code| code.to_string()),
+            self.command,
+            tail.join("\n"),
+            log_path().display()
+        )
+    }
+}
+
+impl error::Error for Failure {}
+
+enum Line {
+    Stdout(String),
+    Stderr(String),
+}
+
+/// Run the command, streaming its output live beneath a spinner and capturing all of it to this run's log file.
+pub(crate) fn run(title: &str, command: &mut Command) -> Result<Output> {
+    run_until(title, command, None)
+}
+
+/// Like `run`, but kill the command and fail if it's still running after `timeout`.
+pub(crate) fn run_with_timeout(
+    title: &str,
+    command: &mut Command,
+    timeout: Duration,
+) -> Result<Output> {
+    run_until(title, command, Some(timeout))
+}
+
+fn run_until(title: &str, command: &mut Command, timeout: Option<Duration>) -> Result<Output> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut log = log_file()?
+        .lock()
+        .map_err(|_| anyhow!("Log file lock poisoned."))?;
+    writeln!(log, "==> {title}: {command:?}")?;
+
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| anyhow!("Failed to start {command:?}: {error}"))?;
+    let (sender, receiver) = mpsc::channel();
+    let stdout = child
+        .stdout
+        .take()
+        .map(|stdout| forward(stdout, sender.clone(), Line::Stdout));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|stderr| forward(stderr, sender, Line::Stderr));
+
+    let mut output = Output {
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+    };
+    // Show the spinner and output only at the default verbosity or above; the JSON log gets them regardless.
+    let show = logging::shows(Level::Info);
+    let mut frame = 0;
+    let mut timed_out = false;
+    loop {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            child.kill()?;
+            timed_out = true;
+            break;
+        }
+        if show {
+            print!("\r{title}...{}", SPINNER[frame % SPINNER.len()]);
+            io::stdout().flush()?;
+        }
+        match receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(line) => {
+                let (text, lines) = match line {
+                    Line::Stdout(text) => (text, &mut output.stdout),
+                    Line::Stderr(text) => (text, &mut output.stderr),
+                };
+                // Clear the spinner, then print the line in its place.
+                if show {
+                    println!("\r\x1b[2K{text}");
+                }
+                logging::record(Level::Info, &text);
+                writeln!(log, "{text}")?;
+                lines.push(text);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => frame += 1,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    // Anything the killed command started may still hold its output open, so leave the readers to finish alone.
+    if !timed_out {
+        for reader in stdout.into_iter().chain(stderr) {
+            reader
+                .join()
+                .map_err(|_| anyhow!("Output reader panicked."))??;
+        }
+    }
+
+    let status = child.wait()?;
+    writeln!(log, "<== {status}")?;
+    let result = if timed_out {
+        "timed out"
+    } else if status.success() {
+        "done"
     } else {
-        println!("failed.");
-        Err(anyhow!("Command failed: {:#?}", command))
+        "failed"
+    };
+    if show {
+        println!("\r\x1b[2K{title}...{result}.");
+    }
+    logging::record(Level::Info, &format!("{title}...{result}."));
+    if let (true, Some(timeout)) = (timed_out, timeout) {
+        Err(anyhow!(
+            "{title} timed out after {} seconds: {command:?}\n(Full output in '{}'.)",
+            timeout.as_secs(),
+            log_path().display()
+        ))
+    } else if status.success() {
+        Ok(output)
+    } else {
+        Err(Failure {
+            command: format!("{command:?}"),
+            exit_code: status.code(),
+            output,
+        }
+        .into())
+    }
+}
+
+/// Send each line read from the stream to the receiver on its own thread, so stdout and stderr can't deadlock.
+fn forward(
+    stream: impl Read + Send + 'static,
+    sender: mpsc::Sender<Line>,
+    line: fn(String) -> Line,
+) -> thread::JoinHandle<io::Result<()>> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut buffer = Vec::new();
+        while reader.read_until(b'\n', &mut buffer)? > 0 {
+            let text = String::from_utf8_lossy(&buffer).trim_end().to_string();
+            // The receiver only goes away once we're done, so there's nothing to do about a failed send.
+            let _ = sender.send(line(text));
+            buffer.clear();
+        }
+        Ok(())
+    })
+}
+
+/// The log file of this run, e.g. "rover-20260101-120000.log", shared by every command it runs.
+fn log_path() -> &'static Path {
+    static PATH: OnceLock<PathBuf> = OnceLock::new();
+    PATH.get_or_init(|| log_folder().join(format!("rover-{}.log", time_stamp::now())))
+}
+
+/// The configured log folder. Commands only run once the config has loaded, so the default is a formality.
+fn log_folder() -> PathBuf {
+    config::get().map_or_else(|_| Config::default().log_folder(), Config::log_folder)
+}
+
+fn log_file() -> Result<&'static Mutex<fs::File>> {
+    static FILE: OnceLock<Mutex<fs::File>> = OnceLock::new();
+    if let Some(file) = FILE.get() {
+        return Ok(file);
     }
+    fs::create_dir_all(log_folder())?;
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path())?;
+    Ok(FILE.get_or_init(|| Mutex::new(file)))
 }
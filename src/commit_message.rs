@@ -0,0 +1,128 @@
+use crate::{config::Config, diff::LayoutDiff, git, layout::Layout, source::OryxExport};
+use anyhow::Result;
+
+const SOURCE_TRAILER: &str = "Oryx-Source: ";
+const REVISION_TRAILER: &str = "Oryx-Revision: ";
+
+/// Compose a commit message describing what changed since the export of the previous commit.
+pub(crate) fn compose(config: &Config, export: &OryxExport) -> Result<String> {
+    let new = Layout::of(export)?;
+    let previous = previous_source(config).and_then(|previous| {
+        // The previous download may have been deleted since, in which case there's nothing to diff against.
+        Layout::read(&previous)
+            .ok()
+            .map(|layout| (previous, layout))
+    });
+
+    let mut message = String::new();
+    match &previous {
+        None => message.push_str("Import Oryx layout\n"),
+        Some((previous, old)) => {
+            let diff = LayoutDiff::new(old, &new);
+            message.push_str(&summarize(&diff));
+            message.push_str("\n\n");
+            message.push_str(&format!("Changes since '{previous}':\n"));
+            message.push_str(&diff.to_string());
+        }
+    }
+    message.push('\n');
+    // A download's file name, or the full path of any other input, so the next run can find it again.
+    message.push_str(&format!("{SOURCE_TRAILER}{}\n", export.name));
+    if let Some((layout, revision)) = new.revision() {
+        message.push_str(&format!("{REVISION_TRAILER}{layout}/{revision}\n"));
+    }
+    Ok(message)
+}
+
+/// One line naming the layers touched, macros added and removed, and settings changed.
+fn summarize(diff: &LayoutDiff) -> String {
+    let mut parts = Vec::new();
+    let layers = diff.layers_touched();
+    if !layers.is_empty() {
+        let layers = layers.iter().map(usize::to_string).collect::<Vec<_>>();
+        parts.push(format!(
+            "{} {}",
+            if layers.len() == 1 { "layer" } else { "layers" },
+            layers.join(", ")
+        ));
+    }
+    if !diff.macros_added.is_empty() {
+        parts.push(format!("add macros {}", quote(&diff.macros_added)));
+    }
+    if !diff.macros_removed.is_empty() {
+        parts.push(format!("remove macros {}", quote(&diff.macros_removed)));
+    }
+    if !diff.tap_dances.is_empty() {
+        parts.push("tap dances".to_string());
+    }
+    if !diff.config.is_empty() {
+        parts.push("config.h".to_string());
+    }
+    if !diff.rules.is_empty() {
+        parts.push("rules.mk".to_string());
+    }
+    if parts.is_empty() {
+        "Regenerate Oryx layout with no changes".to_string()
+    } else {
+        format!("Update {}", parts.join("; "))
+    }
+}
+
+fn quote(texts: &[String]) -> String {
+    texts
+        .iter()
+        .map(|text| format!("\"{text}\""))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The input committed last, from its trailer or, for older commits, the whole message.
+fn previous_source(config: &Config) -> Option<String> {
+    let message = git::head_message(&config.export_folder())?;
+    message
+        .lines()
+        .find_map(|line| line.strip_prefix(SOURCE_TRAILER))
+        .or_else(|| Some(message.trim()).filter(|message| message.ends_with(".zip")))
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::Repository;
+    use std::fs;
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/moonlander_source"
+    );
+
+    #[test]
+    fn diffs_against_input_given_by_path() {
+        let qmk_firmware = std::env::temp_dir().join("rover-commit-message");
+        let _ = fs::remove_dir_all(&qmk_firmware);
+        let config = Config {
+            qmk_firmware,
+            ..Config::default()
+        };
+        fs::create_dir_all(config.export_folder()).unwrap();
+        let repository = Repository::init(&config.qmk_firmware).unwrap();
+        let export = OryxExport::read(FIXTURE).unwrap();
+
+        let first = compose(&config, &export).unwrap();
+        assert!(first.starts_with("Import Oryx layout\n"));
+        assert!(first.contains(&format!("{SOURCE_TRAILER}{FIXTURE}\n")));
+
+        let signature = git2::Signature::now("rover", "rover@example.com").unwrap();
+        let tree = repository
+            .find_tree(repository.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        repository
+            .commit(Some("HEAD"), &signature, &signature, &first, &tree, &[])
+            .unwrap();
+
+        let second = compose(&config, &export).unwrap();
+        assert!(second.starts_with("Regenerate Oryx layout with no changes\n"));
+        assert!(second.contains(&format!("Changes since '{FIXTURE}':")));
+    }
+}
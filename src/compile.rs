@@ -0,0 +1,105 @@
+use crate::{command, config::Config, diagnostics};
+use anyhow::{anyhow, Result};
+use std::{process::Command, thread};
+
+/// A way of compiling qmk_firmware for a keyboard and keymap.
+pub(crate) trait BuildBackend {
+    /// The command which compiles the configured keyboard and keymap.
+    fn command(&self, config: &Config) -> Command;
+}
+
+/// `qmk compile` from a QMK CLI on the PATH.
+struct Qmk;
+
+impl BuildBackend for Qmk {
+    fn command(&self, config: &Config) -> Command {
+        let mut command = Command::new("qmk");
+        command.args([
+            "compile",
+            "-j",
+            "0",
+            "-kb",
+            &config.keyboard,
+            "-km",
+            &config.keymap,
+        ]);
+        command
+    }
+}
+
+/// Plain `make <keyboard>:<keymap>` inside the qmk_firmware checkout, for when the QMK CLI isn't installed.
+struct Make;
+
+impl BuildBackend for Make {
+    fn command(&self, config: &Config) -> Command {
+        let jobs = thread::available_parallelism().map_or(1, usize::from);
+        let mut command = Command::new("make");
+        command
+            .current_dir(&config.qmk_firmware)
+            .arg(format!("-j{jobs}"))
+            .arg(format!("{}:{}", config.keyboard, config.keymap));
+        command
+    }
+}
+
+/// `make` inside a Docker or Podman container with the QMK toolchain, with qmk_firmware mounted into it.
+struct Container;
+
+impl BuildBackend for Container {
+    fn command(&self, config: &Config) -> Command {
+        let mut command = Command::new(&config.container_runtime);
+        command
+            .args(["run", "--rm", "-v"])
+            .arg(format!("{}:/qmk_firmware", config.qmk_firmware.display()))
+            .args(["-w", "/qmk_firmware", &config.container_image, "make"])
+            .arg(format!("{}:{}", config.keyboard, config.keymap));
+        command
+    }
+}
+
+/// `qmk compile` within the QMK MSYS bash shell on Windows.
+struct Msys;
+
+impl BuildBackend for Msys {
+    fn command(&self, config: &Config) -> Command {
+        let mut command = Command::new(config.msys_folder.join("usr/bin/bash.exe"));
+        command.args([
+            "-l",
+            "-c",
+            &format!(
+                "qmk compile -j 0 -kb {} -km {}",
+                config.keyboard, config.keymap
+            ),
+        ]);
+        command
+    }
+}
+
+fn backend(name: &str) -> Result<Box<dyn BuildBackend>> {
+    match name {
+        "qmk" => Ok(Box::new(Qmk)),
+        "make" => Ok(Box::new(Make)),
+        "container" => Ok(Box::new(Container)),
+        "msys" => Ok(Box::new(Msys)),
+        _ => Err(anyhow!(
+            "Unknown build backend '{name}'. Expected qmk, make, container or msys."
+        )),
+    }
+}
+
+/// Compile qmk_firmware with the configured build backend, reporting any compiler diagnostics.
+pub(crate) fn compile(config: &Config) -> Result<command::Output> {
+    let compiled = command::run(
+        "Compiling QMK firmware",
+        &mut backend(&config.build_backend)?.command(config),
+    );
+    match &compiled {
+        Ok(output) => diagnostics::report(config, output)?,
+        Err(error) => {
+            if let Some(failure) = error.downcast_ref::<command::Failure>() {
+                diagnostics::report(config, &failure.output)?;
+            }
+        }
+    }
+    compiled
+}
@@ -0,0 +1,234 @@
+use crate::{
+    macros::{Macro, MacroSet},
+    profile::{self, KeyboardProfile},
+};
+use anyhow::{anyhow, Result};
+use enum_iterator::all;
+use std::{collections::BTreeMap, env, fs, path::PathBuf, sync::OnceLock, time::Duration};
+
+// Name of the optional config file, looked for next to the rover executable unless ROVER_CONFIG names another.
+const FILE_NAME: &str = "rover.cfg";
+
+/// Settings read from rover.cfg, one "KEY = value" per line like rules.mk. Anything not set keeps its
+/// hard-coded default.
+pub(crate) struct Config {
+    /// QMK keyboard name, e.g. "moonlander".
+    pub(crate) keyboard: String,
+    /// QMK keymap name, e.g. "chrispetkau".
+    pub(crate) keymap: String,
+    /// Root of the qmk_firmware checkout.
+    pub(crate) qmk_firmware: PathBuf,
+    /// Folder Oryx downloads land in.
+    pub(crate) import_folder: PathBuf,
+    /// Folder rover keeps its logs, build history and manifest of generated files in.
+    pub(crate) data_folder: PathBuf,
+    /// How to compile: "msys", "qmk", "make" or "container".
+    pub(crate) build_backend: String,
+    /// Root of the QMK MSYS installation, for the "msys" build backend.
+    pub(crate) msys_folder: PathBuf,
+    /// "docker" or "podman", for the "container" build backend.
+    pub(crate) container_runtime: String,
+    /// Image with the QMK toolchain, for the "container" build backend.
+    pub(crate) container_image: String,
+    /// How to flash: "wally", "dfu-util", "qmk" or "manual". Defaults to the keyboard profile's flasher.
+    pub(crate) flasher: Option<String>,
+    /// Wally (or Keymapp's wally-cli) executable, for the "wally" flasher.
+    pub(crate) wally: PathBuf,
+    /// How long to wait for the board to enter its bootloader.
+    pub(crate) bootloader_timeout: Duration,
+    /// Warn when the firmware is within this percentage of the keyboard's flash capacity.
+    pub(crate) flash_margin: u64,
+    /// Base URL of the Oryx API, for fetching layouts.
+    pub(crate) oryx_url: String,
+    /// Milliseconds between the keys a macro types.
+    pub(crate) macro_delay: u64,
+    /// Per-macro overrides of `macro_delay`, keyed by Macro name, e.g. "Return", from MACRO_DELAY.Return lines.
+    pub(crate) macro_delays: BTreeMap<String, u64>,
+    /// How a macro's text is written: "taps" for an SS_TAP(...) per character, or "compact" for a string literal
+    /// where the characters allow it.
+    pub(crate) macro_encoding: String,
+    /// What types a macro's text: "SEND_STRING" or "send_string_with_delay".
+    pub(crate) macro_sender: String,
+    /// The macros which the macro codes of a layer resolve against, unless `macro_sets` says otherwise.
+    pub(crate) macro_set: MacroSet,
+    /// Per-layer overrides of `macro_set`, keyed by layer number, from MACRO_SET.1 lines.
+    pub(crate) macro_sets: BTreeMap<usize, MacroSet>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            keyboard: "moonlander".to_string(),
+            keymap: "chrispetkau".to_string(),
+            qmk_firmware: PathBuf::from("C:/src/qmk_firmware"),
+            import_folder: home_folder().join("Downloads"),
+            data_folder: platform_data_folder().join("rover"),
+            build_backend: if cfg!(windows) { "msys" } else { "qmk" }.to_string(),
+            msys_folder: PathBuf::from("C:/QMK_MSYS"),
+            container_runtime: "docker".to_string(),
+            container_image: "ghcr.io/qmk/qmk_cli".to_string(),
+            flasher: None,
+            wally: PathBuf::from(if cfg!(windows) {
+                "C:/Program Files (x86)/Wally/Wally.exe"
+            } else {
+                "wally-cli"
+            }),
+            bootloader_timeout: Duration::from_secs(60),
+            flash_margin: 10,
+            oryx_url: "https://oryx.zsa.io".to_string(),
+            macro_delay: 0,
+            macro_delays: BTreeMap::new(),
+            macro_encoding: "taps".to_string(),
+            macro_sender: "SEND_STRING".to_string(),
+            macro_set: MacroSet::default(),
+            macro_sets: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// The keymap folder rover generates files in, e.g. "<QMK_FIRMWARE>/keyboards/moonlander/keymaps/chrispetkau".
+    pub(crate) fn export_folder(&self) -> PathBuf {
+        self.qmk_firmware
+            .join("keyboards")
+            .join(&self.keyboard)
+            .join("keymaps")
+            .join(&self.keymap)
+    }
+
+    /// Where each run's log of external command output goes.
+    pub(crate) fn log_folder(&self) -> PathBuf {
+        self.data_folder.join("logs")
+    }
+
+    /// Where every flashed build is archived.
+    pub(crate) fn history_folder(&self) -> PathBuf {
+        self.data_folder.join("history")
+    }
+
+    /// Checksums of the files rover last generated.
+    pub(crate) fn manifest_file(&self) -> PathBuf {
+        self.data_folder.join("manifest.txt")
+    }
+
+    pub(crate) fn profile(&self) -> KeyboardProfile {
+        profile::for_keyboard(&self.keyboard)
+    }
+
+    /// The macros the given layer's macro codes resolve against.
+    pub(crate) fn macro_set(&self, layer: usize) -> MacroSet {
+        self.macro_sets
+            .get(&layer)
+            .copied()
+            .unwrap_or(self.macro_set)
+    }
+
+    pub(crate) fn flasher(&self) -> &str {
+        self.flasher
+            .as_deref()
+            .unwrap_or_else(|| self.profile().flasher)
+    }
+
+    fn load() -> Result<Self> {
+        let path = match env::var_os("ROVER_CONFIG") {
+            Some(path) => PathBuf::from(path),
+            None => env::current_exe()?.with_file_name(FILE_NAME),
+        };
+        let mut config = Self::default();
+        if !path.exists() {
+            return Ok(config);
+        }
+        for (i, line) in fs::read_to_string(&path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let location = format!("{}:{}", path.display(), i + 1);
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("{location}: expected KEY = value."))?;
+            let value = value.trim().to_string();
+            let number = || {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| anyhow!("{location}: {} must be a number.", key.trim()))
+            };
+            match key.trim() {
+                "KEYBOARD" => config.keyboard = value,
+                "KEYMAP" => config.keymap = value,
+                "QMK_FIRMWARE" => config.qmk_firmware = PathBuf::from(value),
+                "IMPORT_FOLDER" => config.import_folder = PathBuf::from(value),
+                "DATA_FOLDER" => config.data_folder = PathBuf::from(value),
+                "BUILD_BACKEND" => config.build_backend = value,
+                "MSYS_FOLDER" => config.msys_folder = PathBuf::from(value),
+                "CONTAINER_RUNTIME" => config.container_runtime = value,
+                "CONTAINER_IMAGE" => config.container_image = value,
+                "FLASHER" => config.flasher = Some(value),
+                "WALLY" => config.wally = PathBuf::from(value),
+                "BOOTLOADER_TIMEOUT" => config.bootloader_timeout = Duration::from_secs(number()?),
+                "FLASH_MARGIN" => config.flash_margin = number()?,
+                "ORYX_URL" => config.oryx_url = value.trim_end_matches('/').to_string(),
+                "MACRO_DELAY" => config.macro_delay = number()?,
+                "MACRO_ENCODING" => config.macro_encoding = value,
+                "MACRO_SENDER" => config.macro_sender = value,
+                "MACRO_SET" => {
+                    config.macro_set =
+                        MacroSet::parse(&value).map_err(|error| anyhow!("{location}: {error}"))?
+                }
+                key if key.starts_with("MACRO_DELAY.") => {
+                    let name = &key["MACRO_DELAY.".len()..];
+                    if !all::<Macro>().any(|value| format!("{value:?}") == name) {
+                        return Err(anyhow!("{location}: unknown macro '{name}'."));
+                    }
+                    config.macro_delays.insert(name.to_string(), number()?);
+                }
+                key if key.starts_with("MACRO_SET.") => {
+                    let layer = key["MACRO_SET.".len()..]
+                        .parse::<usize>()
+                        .map_err(|_| anyhow!("{location}: '{key}' names no layer number."))?;
+                    config.macro_sets.insert(
+                        layer,
+                        MacroSet::parse(&value).map_err(|error| anyhow!("{location}: {error}"))?,
+                    );
+                }
+                key => return Err(anyhow!("{location}: unknown setting '{key}'.")),
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// The user's home folder, e.g. "C:/Users/Chris Petkau".
+fn home_folder() -> PathBuf {
+    env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" })
+        .map_or_else(|| PathBuf::from("."), PathBuf::from)
+}
+
+/// The platform's per-user data folder: %LOCALAPPDATA% on Windows, elsewhere $XDG_DATA_HOME or ~/.local/share.
+fn platform_data_folder() -> PathBuf {
+    let folder = if cfg!(windows) {
+        env::var_os("LOCALAPPDATA")
+    } else {
+        env::var_os("XDG_DATA_HOME")
+    };
+    folder.map_or_else(
+        || {
+            if cfg!(windows) {
+                home_folder().join("AppData/Local")
+            } else {
+                home_folder().join(".local/share")
+            }
+        },
+        PathBuf::from,
+    )
+}
+
+/// The config, loaded on first use.
+pub(crate) fn get() -> Result<&'static Config> {
+    static CONFIG: OnceLock<Config> = OnceLock::new();
+    if let Some(config) = CONFIG.get() {
+        return Ok(config);
+    }
+    let config = Config::load()?;
+    Ok(CONFIG.get_or_init(|| config))
+}
@@ -0,0 +1,92 @@
+use crate::{command, config::Config, logging::warning, source_map};
+use anyhow::Result;
+use regex::Regex;
+
+/// A gcc diagnostic such as "keyboards/.../keymap.c:42:5: error: 'X' undeclared".
+pub(crate) struct Diagnostic {
+    pub(crate) file: String,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) severity: String,
+    pub(crate) message: String,
+}
+
+/// Parse every gcc diagnostic out of the output of `qmk compile`.
+pub(crate) fn parse(output: &command::Output) -> Result<Vec<Diagnostic>> {
+    let diagnostic = Regex::new(r"^(.+?):(\d+):(\d+): (fatal error|error|warning|note): (.*)$")?;
+    Ok(output
+        .stdout
+        .iter()
+        .chain(&output.stderr)
+        .filter_map(|line| diagnostic.captures(line))
+        .map(|captures| Diagnostic {
+            file: captures[1].to_string(),
+            line: captures[2].parse().unwrap_or_default(),
+            column: captures[3].parse().unwrap_or_default(),
+            severity: captures[4].to_string(),
+            message: captures[5].to_string(),
+        })
+        .collect())
+}
+
+/// Print each error and warning, annotated with the rover stage that generated the offending line.
+pub(crate) fn report(config: &Config, output: &command::Output) -> Result<()> {
+    let diagnostics = parse(output)?;
+    if diagnostics.is_empty() {
+        return Ok(());
+    }
+    warning!("Compiler diagnostics:");
+    for diagnostic in diagnostics {
+        warning!(
+            "{}:{}:{}: {}: {}",
+            diagnostic.file,
+            diagnostic.line,
+            diagnostic.column,
+            diagnostic.severity,
+            diagnostic.message
+        );
+        let origin = generated_file_name(config, &diagnostic.file)
+            .and_then(|file_name| source_map::lookup(file_name, diagnostic.line));
+        if let Some(origin) = origin {
+            warning!("\tgenerated by {origin}");
+        }
+    }
+    Ok(())
+}
+
+/// The name of the file if it lies directly in the export folder, where rover generates files, rather than being,
+/// say, the keyboard's own config.h. gcc names it relative to qmk_firmware or absolutely (perhaps as seen from a
+/// container), so match the export folder's path within qmk_firmware, e.g. "keyboards/moonlander/keymaps/x/".
+fn generated_file_name<'a>(config: &Config, file: &'a str) -> Option<&'a str> {
+    let export_folder = format!("keyboards/{}/keymaps/{}/", config.keyboard, config.keymap);
+    let (folder, file_name) = file.rsplit_once(['/', '\\'])?;
+    let folder = format!("{}/", folder.replace('\\', "/"));
+    (folder == export_folder || folder.ends_with(&format!("/{export_folder}"))).then_some(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_files_in_export_folder_are_generated() {
+        let config = Config::default();
+        let generated = |file| generated_file_name(&config, file);
+        assert_eq!(
+            generated("keyboards/moonlander/keymaps/chrispetkau/config.h"),
+            Some("config.h")
+        );
+        assert_eq!(
+            generated(
+                "C:\\src\\qmk_firmware\\keyboards\\moonlander\\keymaps\\chrispetkau\\keymap.c"
+            ),
+            Some("keymap.c")
+        );
+        assert_eq!(generated("keyboards/moonlander/config.h"), None);
+        assert_eq!(
+            generated("keyboards/moonlander/keymaps/default/config.h"),
+            None
+        );
+        assert_eq!(generated("config.h"), None);
+    }
+}
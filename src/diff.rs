@@ -0,0 +1,223 @@
+use crate::{layout::Layout, zip};
+use anyhow::{anyhow, Result};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// A key whose keycode differs between two layouts.
+pub(crate) struct KeyChange {
+    pub(crate) layer: usize,
+    pub(crate) key: usize,
+    pub(crate) old: String,
+    pub(crate) new: String,
+}
+
+/// A named value which was added (no old value), removed (no new value) or changed.
+pub(crate) struct SettingChange {
+    pub(crate) name: String,
+    pub(crate) old: Option<String>,
+    pub(crate) new: Option<String>,
+}
+
+/// The semantic differences between two Oryx exports.
+pub(crate) struct LayoutDiff {
+    pub(crate) layers_added: Vec<usize>,
+    pub(crate) layers_removed: Vec<usize>,
+    pub(crate) keys: Vec<KeyChange>,
+    pub(crate) macros_added: Vec<String>,
+    pub(crate) macros_removed: Vec<String>,
+    pub(crate) tap_dances: Vec<SettingChange>,
+    pub(crate) config: Vec<SettingChange>,
+    pub(crate) rules: Vec<SettingChange>,
+}
+
+impl LayoutDiff {
+    pub(crate) fn new(old: &Layout, new: &Layout) -> Self {
+        let mut keys = Vec::new();
+        for (&layer, new_keys) in &new.layers {
+            let Some(old_keys) = old.layers.get(&layer) else {
+                continue;
+            };
+            for key in 0..old_keys.len().max(new_keys.len()) {
+                let old_keycode = old_keys.get(key).map_or("(none)", String::as_str);
+                let new_keycode = new_keys.get(key).map_or("(none)", String::as_str);
+                // Macro indices are renumbered by Oryx, so compare what the macro types.
+                let old_keycode = old.describe_keycode(old_keycode);
+                let new_keycode = new.describe_keycode(new_keycode);
+                if strip_macro_index(&old_keycode) != strip_macro_index(&new_keycode) {
+                    keys.push(KeyChange {
+                        layer,
+                        key,
+                        old: old_keycode,
+                        new: new_keycode,
+                    });
+                }
+            }
+        }
+
+        let old_macros = old.macros.values().collect::<BTreeSet<_>>();
+        let new_macros = new.macros.values().collect::<BTreeSet<_>>();
+
+        let flatten = |layout: &Layout| {
+            layout
+                .tap_dances
+                .iter()
+                .flat_map(|(dance, steps)| {
+                    steps.iter().map(move |(step, actions)| {
+                        (format!("DANCE_{dance} {step}"), actions.clone())
+                    })
+                })
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        Self {
+            layers_added: new
+                .layers
+                .keys()
+                .filter(|layer| !old.layers.contains_key(layer))
+                .copied()
+                .collect(),
+            layers_removed: old
+                .layers
+                .keys()
+                .filter(|layer| !new.layers.contains_key(layer))
+                .copied()
+                .collect(),
+            keys,
+            macros_added: new_macros
+                .difference(&old_macros)
+                .map(|text| text.to_string())
+                .collect(),
+            macros_removed: old_macros
+                .difference(&new_macros)
+                .map(|text| text.to_string())
+                .collect(),
+            tap_dances: diff_settings(&flatten(old), &flatten(new)),
+            config: diff_settings(&old.config, &new.config),
+            rules: diff_settings(&old.rules, &new.rules),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.layers_added.is_empty()
+            && self.layers_removed.is_empty()
+            && self.keys.is_empty()
+            && self.macros_added.is_empty()
+            && self.macros_removed.is_empty()
+            && self.tap_dances.is_empty()
+            && self.config.is_empty()
+            && self.rules.is_empty()
+    }
+
+    /// Every layer which was added, removed or had a key changed.
+    pub(crate) fn layers_touched(&self) -> BTreeSet<usize> {
+        self.layers_added
+            .iter()
+            .chain(&self.layers_removed)
+            .copied()
+            .chain(self.keys.iter().map(|key| key.layer))
+            .collect()
+    }
+}
+
+fn strip_macro_index(keycode: &str) -> &str {
+    match keycode.strip_prefix("ST_MACRO_") {
+        Some(rest) if rest.contains('"') => rest.trim_start_matches(|c: char| c.is_ascii_digit()),
+        _ => keycode,
+    }
+}
+
+fn diff_settings(
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+) -> Vec<SettingChange> {
+    old.keys()
+        .chain(new.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|name| {
+            let old = old.get(name);
+            let new = new.get(name);
+            if old == new {
+                None
+            } else {
+                Some(SettingChange {
+                    name: name.clone(),
+                    old: old.cloned(),
+                    new: new.cloned(),
+                })
+            }
+        })
+        .collect()
+}
+
+impl fmt::Display for SettingChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.old, &self.new) {
+            (None, Some(new)) => write!(f, "added {} = {new}", self.name),
+            (Some(old), None) => write!(f, "removed {} (was {old})", self.name),
+            (Some(old), Some(new)) => write!(f, "changed {} from {old} to {new}", self.name),
+            (None, None) => write!(f, "{} unchanged", self.name),
+        }
+    }
+}
+
+impl fmt::Display for LayoutDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No changes.");
+        }
+        for layer in &self.layers_added {
+            writeln!(f, "Layer {layer}: added.")?;
+        }
+        for layer in &self.layers_removed {
+            writeln!(f, "Layer {layer}: removed.")?;
+        }
+        for key in &self.keys {
+            writeln!(
+                f,
+                "Layer {} key {}: {} -> {}",
+                key.layer, key.key, key.old, key.new
+            )?;
+        }
+        for text in &self.macros_added {
+            writeln!(f, "Macro added: \"{text}\"")?;
+        }
+        for text in &self.macros_removed {
+            writeln!(f, "Macro removed: \"{text}\"")?;
+        }
+        for change in &self.tap_dances {
+            writeln!(f, "Tap dance: {change}")?;
+        }
+        for change in &self.config {
+            writeln!(f, "config.h: {change}")?;
+        }
+        for change in &self.rules {
+            writeln!(f, "rules.mk: {change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Print the semantic diff between two downloaded .zip files, defaulting to the two most recent downloads.
+pub(crate) fn run(old: Option<String>, new: Option<String>) -> Result<()> {
+    let (old, new) = match (old, new) {
+        (Some(old), Some(new)) => (old, new),
+        (None, None) => {
+            let mut downloads = zip::find_most_recent_downloads(2)?;
+            if downloads.len() < 2 {
+                return Err(anyhow!("Need two moonlander_* .zip files to diff."));
+            }
+            let old = downloads.pop().unwrap();
+            (old, downloads.pop().unwrap())
+        }
+        _ => return Err(anyhow!("Usage: rover diff [<old.zip> <new.zip>]")),
+    };
+    println!("Comparing '{old}' to '{new}'...");
+    print!(
+        "{}",
+        LayoutDiff::new(&Layout::read(&old)?, &Layout::read(&new)?)
+    );
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use crate::{
+    checksum,
+    config::Config,
+    logging::{info, progress},
+};
+use anyhow::{anyhow, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+// Image formats QMK produces, depending on the keyboard's bootloader.
+const EXTENSIONS: [&str; 3] = ["bin", "hex", "uf2"];
+
+// A .uf2 is a series of blocks of this many bytes, each carrying a payload whose size is at PAYLOAD_SIZE_OFFSET.
+const UF2_BLOCK_BYTES: usize = 512;
+const UF2_PAYLOAD_SIZE_OFFSET: usize = 16;
+
+// Slack for filesystems which round modification times, e.g. FAT's two seconds.
+const MTIME_TOLERANCE: Duration = Duration::from_secs(2);
+
+/// A firmware image produced by the build.
+pub(crate) struct Firmware {
+    pub(crate) path: PathBuf,
+    /// Bytes the image writes to flash, not the size of the file holding it.
+    pub(crate) size: u64,
+    pub(crate) sha256: String,
+}
+
+/// Find the image this build produced, e.g. "moonlander_chrispetkau.bin" in the root of qmk_firmware, refusing
+/// one older than the build (i.e. left over from a previous build).
+pub(crate) fn locate(config: &Config, build_start: SystemTime) -> Result<Firmware> {
+    progress!("Locating firmware...");
+    let stem = format!("{}_{}", config.keyboard.replace('/', "_"), config.keymap);
+    let (path, modified) = EXTENSIONS
+        .iter()
+        .filter_map(|extension| {
+            let path = config.qmk_firmware.join(format!("{stem}.{extension}"));
+            let modified = fs::metadata(&path).ok()?.modified().ok()?;
+            Some((path, modified))
+        })
+        .max_by_key(|(_, modified)| *modified)
+        .ok_or_else(|| {
+            anyhow!(
+                "No {stem}.{{{}}} in '{}'.",
+                EXTENSIONS.join(","),
+                config.qmk_firmware.display()
+            )
+        })?;
+    if modified + MTIME_TOLERANCE < build_start {
+        info!("stale.");
+        return Err(anyhow!(
+            "'{}' predates this build, so compiling didn't produce it. Refusing to flash a stale image.",
+            path.display()
+        ));
+    }
+
+    let contents = fs::read(&path)?;
+    let firmware = Firmware {
+        size: flashed_bytes(&path, &contents),
+        sha256: checksum::sha256(&contents),
+        path,
+    };
+    info!(
+        "found '{}' ({} bytes, SHA-256 {}).",
+        firmware.path.display(),
+        firmware.size,
+        firmware.sha256
+    );
+    Ok(firmware)
+}
+
+/// Bytes the image writes to flash: all of a .bin, the data records of a .hex, or the payloads of a .uf2's blocks.
+fn flashed_bytes(path: &Path, contents: &[u8]) -> u64 {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("hex") => String::from_utf8_lossy(contents)
+            .lines()
+            .filter(|record| record.len() >= 9 && &record[7..9] == "00")
+            .filter_map(|record| u64::from_str_radix(&record[1..3], 16).ok())
+            .sum(),
+        Some("uf2") => contents
+            .chunks_exact(UF2_BLOCK_BYTES)
+            .map(|block| {
+                let size = &block[UF2_PAYLOAD_SIZE_OFFSET..UF2_PAYLOAD_SIZE_OFFSET + 4];
+                u64::from(u32::from_le_bytes([size[0], size[1], size[2], size[3]]))
+            })
+            .sum(),
+        _ => contents.len() as u64,
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{
+    command,
+    config::Config,
+    logging::{info, progress},
+};
+use anyhow::{anyhow, Result};
+use std::{
+    io::{self, Read, Write},
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+// How long writing the firmware may take once the board is in its bootloader.
+const FLASH_TIMEOUT: Duration = Duration::from_secs(120);
+
+// How often to poll for the bootloader.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Treat a `dfu-util -l` which hasn't finished after this long as having found nothing.
+const LIST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A way of writing a firmware image to the keyboard.
+pub(crate) trait Flasher {
+    /// Whether the board must already be in its bootloader before flashing starts. Flashers like Wally
+    /// wait for the bootloader themselves.
+    fn needs_bootloader(&self) -> bool {
+        false
+    }
+
+    /// Check the keyboard's profile has what this flasher needs, before asking the user to reset the board.
+    fn validate(&self, _config: &Config) -> Result<()> {
+        Ok(())
+    }
+
+    fn flash(&self, config: &Config, firmware: &Path) -> Result<()>;
+}
+
+/// Wally, or Keymapp's wally-cli, which prompts for and waits on the reset button itself.
+struct Wally;
+
+impl Flasher for Wally {
+    fn flash(&self, config: &Config, firmware: &Path) -> Result<()> {
+        command::run_with_timeout(
+            "Flashing keyboard",
+            Command::new(&config.wally).arg(firmware),
+            config.bootloader_timeout + FLASH_TIMEOUT,
+        )?;
+        Ok(())
+    }
+}
+
+/// dfu-util, for boards with an STM32 DFU bootloader.
+struct DfuUtil;
+
+impl Flasher for DfuUtil {
+    fn needs_bootloader(&self) -> bool {
+        true
+    }
+
+    fn validate(&self, config: &Config) -> Result<()> {
+        dfu_target(config).map(|_| ())
+    }
+
+    fn flash(&self, config: &Config, firmware: &Path) -> Result<()> {
+        let (usb_id, address) = dfu_target(config)?;
+        command::run_with_timeout(
+            "Flashing keyboard",
+            Command::new("dfu-util")
+                .args(["-a", "0", "-d", usb_id, "-s", address, "-D"])
+                .arg(firmware),
+            FLASH_TIMEOUT,
+        )?;
+        Ok(())
+    }
+}
+
+/// The bootloader USB id and DFU address of the keyboard.
+fn dfu_target(config: &Config) -> Result<(&'static str, &'static str)> {
+    let profile = config.profile();
+    if profile.bootloader_usb_id.is_empty() {
+        return Err(anyhow!(
+            "'{}' has no known bootloader USB id, so dfu-util can't flash it.",
+            config.keyboard
+        ));
+    }
+    let address = profile
+        .dfu_address
+        .ok_or_else(|| anyhow!("'{}' has no DFU bootloader.", config.keyboard))?;
+    Ok((profile.bootloader_usb_id, address))
+}
+
+/// `qmk flash`, which detects the bootloader itself.
+struct Qmk;
+
+impl Flasher for Qmk {
+    fn flash(&self, config: &Config, firmware: &Path) -> Result<()> {
+        command::run_with_timeout(
+            "Flashing keyboard",
+            Command::new("qmk").arg("flash").arg(firmware),
+            config.bootloader_timeout + FLASH_TIMEOUT,
+        )?;
+        Ok(())
+    }
+}
+
+/// Print instructions and wait for the user to flash by whatever means they like.
+struct Manual;
+
+impl Flasher for Manual {
+    fn flash(&self, _config: &Config, firmware: &Path) -> Result<()> {
+        println!(
+            "Flash '{}' to the keyboard, then press Enter.",
+            firmware.display()
+        );
+        io::stdin().read_line(&mut String::new())?;
+        Ok(())
+    }
+}
+
+fn flasher(name: &str) -> Result<Box<dyn Flasher>> {
+    match name {
+        "wally" => Ok(Box::new(Wally)),
+        "dfu-util" => Ok(Box::new(DfuUtil)),
+        "qmk" => Ok(Box::new(Qmk)),
+        "manual" => Ok(Box::new(Manual)),
+        _ => Err(anyhow!(
+            "Unknown flasher '{name}'. Expected wally, dfu-util, qmk or manual."
+        )),
+    }
+}
+
+/// Flash the firmware with the flasher of the keyboard's profile, unless the config overrides it.
+pub(crate) fn flash(config: &Config, firmware: &Path) -> Result<()> {
+    let flasher = flasher(config.flasher())?;
+    flasher.validate(config)?;
+    if flasher.needs_bootloader() {
+        wait_for_bootloader(config)?;
+    }
+    flasher.flash(config, firmware)
+}
+
+/// Poll `dfu-util -l` until the board shows up with its bootloader's USB id, or time out.
+fn wait_for_bootloader(config: &Config) -> Result<()> {
+    let (usb_id, _) = dfu_target(config)?;
+    let deadline = Instant::now() + config.bootloader_timeout;
+    progress!("Waiting for the keyboard's bootloader; press its reset button...");
+    loop {
+        io::stdout().flush()?;
+        if list_dfu_devices()?.contains(&format!("[{usb_id}]")) {
+            info!("found.");
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            info!("timed out.");
+            return Err(anyhow!(
+                "Keyboard not in bootloader mode after {} seconds.",
+                config.bootloader_timeout.as_secs()
+            ));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// The output of `dfu-util -l`, or nothing if it hangs.
+fn list_dfu_devices() -> Result<String> {
+    let mut child = Command::new("dfu-util")
+        .arg("-l")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|error| anyhow!("Failed to start dfu-util: {error}"))?;
+    let deadline = Instant::now() + LIST_TIMEOUT;
+    // The listing is far smaller than a pipe's buffer, so it can't block dfu-util before it exits.
+    while child.try_wait()?.is_none() {
+        if Instant::now() >= deadline {
+            child.kill()?;
+            child.wait()?;
+            return Ok(String::new());
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    let mut listing = String::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        stdout.read_to_string(&mut listing)?;
+    }
+    Ok(listing)
+}
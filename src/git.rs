@@ -0,0 +1,177 @@
+use crate::{
+    logging::{info, progress},
+    time_stamp,
+};
+use anyhow::{anyhow, Result};
+use git2::{Repository, Status, StatusOptions};
+use std::path::{Path, PathBuf};
+
+/// The message of the most recent commit in the repository containing the export folder.
+pub(crate) fn head_message(export_folder: &Path) -> Option<String> {
+    let repository = Repository::discover(export_folder).ok()?;
+    let head = repository.head().ok()?.peel_to_commit().ok()?;
+    head.message().map(str::to_string)
+}
+
+/// The id of the most recent commit in the repository containing the export folder.
+pub(crate) fn head_id(export_folder: &Path) -> Option<git2::Oid> {
+    let repository = Repository::discover(export_folder).ok()?;
+    let id = repository.head().ok()?.peel_to_commit().ok()?.id();
+    Some(id)
+}
+
+/// Stage exactly the given files of the export folder and commit them, optionally tagging the commit.
+/// Returns None without committing when the generated files are unchanged, tagging HEAD instead.
+pub(crate) fn commit(
+    export_folder: &Path,
+    file_names: &[&str],
+    message: &str,
+    tag: bool,
+) -> Result<Option<git2::Oid>> {
+    progress!("Committing changes...");
+    let repository = Repository::discover(export_folder)?;
+    let work_dir = repository
+        .workdir()
+        .ok_or_else(|| {
+            anyhow!(
+                "Repository containing '{}' is bare.",
+                export_folder.display()
+            )
+        })?
+        .canonicalize()?;
+    let export_folder = export_folder.canonicalize()?;
+    let relative_paths = file_names
+        .iter()
+        .map(|file_name| {
+            Ok(export_folder
+                .join(file_name)
+                .strip_prefix(&work_dir)?
+                .to_path_buf())
+        })
+        .collect::<Result<Vec<PathBuf>>>()?;
+
+    refuse_unrelated_changes(&repository, &work_dir, &export_folder, &relative_paths)?;
+
+    let mut index = repository.index()?;
+    for path in &relative_paths {
+        index.add_path(path)?;
+    }
+    index.write()?;
+    let tree = repository.find_tree(index.write_tree()?)?;
+
+    let parent = match repository.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(_) => None,
+    };
+    if let Some(parent) = parent
+        .as_ref()
+        .filter(|parent| parent.tree_id() == tree.id())
+    {
+        info!("nothing to commit.");
+        if tag {
+            tag_commit(&repository, parent.id())?;
+        }
+        return Ok(None);
+    }
+
+    let signature = repository.signature()?;
+    let parents = parent.iter().collect::<Vec<_>>();
+    let commit = repository.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )?;
+    info!("committed {commit}.");
+
+    if tag {
+        tag_commit(&repository, commit)?;
+    }
+    Ok(Some(commit))
+}
+
+/// Tag the commit with the time it was flashed.
+fn tag_commit(repository: &Repository, commit: git2::Oid) -> Result<()> {
+    let name = format!("flash-{}", time_stamp::now());
+    repository.tag_lightweight(&name, &repository.find_object(commit, None)?, false)?;
+    info!("Tagged commit {commit} as '{name}'.");
+    Ok(())
+}
+
+/// Fail if committing would also commit changes rover didn't make (i.e. anything already staged), or
+/// would leave hand edits to other tracked files of the export folder behind.
+fn refuse_unrelated_changes(
+    repository: &Repository,
+    work_dir: &Path,
+    export_folder: &Path,
+    generated: &[PathBuf],
+) -> Result<()> {
+    let export_folder = export_folder.strip_prefix(work_dir)?;
+    let staged = Status::INDEX_NEW
+        | Status::INDEX_MODIFIED
+        | Status::INDEX_DELETED
+        | Status::INDEX_RENAMED
+        | Status::INDEX_TYPECHANGE;
+    let modified =
+        Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE;
+    let unrelated = repository
+        .statuses(Some(StatusOptions::new().include_untracked(false)))?
+        .iter()
+        .filter_map(|entry| {
+            let path = PathBuf::from(entry.path()?);
+            let status = entry.status();
+            let dirty = status.intersects(staged)
+                || (status.intersects(modified) && path.starts_with(export_folder));
+            if dirty && !generated.contains(&path) {
+                Some(path.display().to_string())
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    if unrelated.is_empty() {
+        Ok(())
+    } else {
+        info!("refused.");
+        Err(anyhow!(
+            "Refusing to commit over unrelated changes: {}",
+            unrelated.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn tags_head_when_nothing_changed() {
+        let work_dir = std::env::temp_dir().join("rover-git-tag");
+        let _ = fs::remove_dir_all(&work_dir);
+        let export_folder = work_dir.join("keymaps/rover");
+        fs::create_dir_all(&export_folder).unwrap();
+        let repository = Repository::init(&work_dir).unwrap();
+        let mut git_config = repository.config().unwrap();
+        git_config.set_str("user.name", "rover").unwrap();
+        git_config
+            .set_str("user.email", "rover@example.com")
+            .unwrap();
+        fs::write(export_folder.join("keymap.c"), "").unwrap();
+
+        let first = commit(&export_folder, &["keymap.c"], "Import", false).unwrap();
+        assert!(first.is_some());
+        assert!(commit(&export_folder, &["keymap.c"], "Again", true)
+            .unwrap()
+            .is_none());
+        let tags = repository.tag_names(Some("flash-*")).unwrap();
+        assert_eq!(tags.len(), 1);
+        let tagged = repository
+            .revparse_single(tags.get(0).unwrap())
+            .unwrap()
+            .id();
+        assert_eq!(Some(tagged), first);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{
+    checksum,
+    config::Config,
+    firmware::Firmware,
+    flash,
+    logging::{info, progress},
+    source, time_stamp, GENERATED_FILES,
+};
+use anyhow::{anyhow, Result};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const METADATA: &str = "metadata.txt";
+const SHA256_PREFIX: &str = "sha256:";
+
+/// Archive the source .zip file (or keymap.c, config.h and rules.mk), generated files and firmware of a flashed build, with a metadata.txt recording when
+/// it was built, its commit and the checksum of every archived file.
+pub(crate) fn archive(
+    config: &Config,
+    zip: &str,
+    firmware: &Firmware,
+    commit: Option<git2::Oid>,
+) -> Result<()> {
+    let time_stamp = time_stamp::now();
+    let folder = config.history_folder().join(&time_stamp);
+    progress!("Archiving build to '{}'...", folder.display());
+    fs::create_dir_all(folder.join("generated"))?;
+
+    let mut files = vec![(firmware.path.clone(), file_name(&firmware.path)?)];
+    for source in source::files(zip)? {
+        let archived = file_name(&source)?;
+        files.push((source, archived));
+    }
+    for generated in GENERATED_FILES {
+        files.push((
+            config.export_folder().join(generated),
+            format!("generated/{generated}"),
+        ));
+    }
+
+    let mut metadata = vec![
+        ("timestamp".to_string(), time_stamp),
+        ("keyboard".to_string(), config.keyboard.clone()),
+        ("keymap".to_string(), config.keymap.clone()),
+        (
+            "commit".to_string(),
+            commit.map_or("none".to_string(), |commit| commit.to_string()),
+        ),
+        ("source".to_string(), file_name(Path::new(zip))?),
+        ("firmware".to_string(), files[0].1.clone()),
+        ("firmware_size".to_string(), firmware.size.to_string()),
+    ];
+    for (source, archived) in &files {
+        fs::copy(source, folder.join(archived))?;
+        metadata.push((
+            format!("{SHA256_PREFIX}{archived}"),
+            checksum::sha256_file(source)?,
+        ));
+    }
+    fs::write(
+        folder.join(METADATA),
+        metadata
+            .iter()
+            .map(|(key, value)| format!("{key} = {value}\n"))
+            .collect::<String>(),
+    )?;
+    info!("done.");
+    Ok(())
+}
+
+/// Re-flash the firmware archived `back` builds before the most recent one, without recompiling.
+pub(crate) fn rollback(config: &Config, back: Option<String>) -> Result<()> {
+    let back = match back {
+        Some(back) => back
+            .parse::<usize>()
+            .map_err(|_| anyhow!("Usage: rover rollback [<number of builds back>]"))?,
+        None => 1,
+    };
+    let mut builds = fs::read_dir(config.history_folder())?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.join(METADATA).is_file())
+        .collect::<Vec<PathBuf>>();
+    // Folders are named by time stamp, so sort newest first by name.
+    builds.sort_by(|a, b| b.cmp(a));
+    let folder = builds.get(back).ok_or_else(|| {
+        anyhow!(
+            "Only {} archived builds; can't go back {back}.",
+            builds.len()
+        )
+    })?;
+
+    let metadata = read_metadata(folder)?;
+    let get = |key: &str| {
+        metadata
+            .get(key)
+            .ok_or_else(|| anyhow!("'{}' has no {key}.", folder.join(METADATA).display()))
+    };
+    let firmware = get("firmware")?;
+    let path = folder.join(firmware);
+    if checksum::sha256_file(&path)? != *get(&format!("{SHA256_PREFIX}{firmware}"))? {
+        return Err(anyhow!(
+            "'{}' doesn't match its archived checksum. Refusing to flash it.",
+            path.display()
+        ));
+    }
+    info!(
+        "Rolling back to the build of {} from '{}' (commit {}).",
+        get("timestamp")?,
+        get("source")?,
+        get("commit")?
+    );
+    flash::flash(config, &path)
+}
+
+fn read_metadata(folder: &Path) -> Result<BTreeMap<String, String>> {
+    Ok(fs::read_to_string(folder.join(METADATA))?
+        .lines()
+        .filter_map(|line| line.split_once(" = "))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect())
+}
+
+fn file_name(path: &Path) -> Result<String> {
+    Ok(path
+        .file_name()
+        .ok_or_else(|| anyhow!("'{}' has no file name.", path.display()))?
+        .to_string_lossy()
+        .to_string())
+}
@@ -1,11 +1,17 @@
-use crate::{custom_keycode::CustomKeycode, macros::Macro, qmk_name, temp_folder, EXPORT_FOLDER};
+use crate::{
+    config::Config,
+    custom_keycode::CustomKeycode,
+    logging::{debug, detail, info, warning},
+    macros::{Macro, MacroSet},
+    qmk_name, report,
+    source_map::{Stage, TrackedFile},
+};
 use anyhow::{anyhow, Result};
 use enum_iterator::all;
 use regex::{Captures, Regex};
 use std::{
-    fs,
-    io::{self, BufRead, Write},
-    path::Path,
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
 };
 
 enum KeymapSection {
@@ -19,12 +25,15 @@ enum KeymapSection {
     TapDanceDefs,
 }
 
-pub(crate) fn update_keymap_c() -> Result<()> {
-    println!("Updating keymap.c...");
-    let input = &mut fs::File::open(Path::new(temp_folder::NAME).join("keymap.c"))?;
-    let keymap_c = &mut fs::File::create(Path::new(EXPORT_FOLDER).join("keymap.c"))?;
-    let petkau_tap_dance_inl =
-        &mut fs::File::create(Path::new(EXPORT_FOLDER).join("petkau_tap_dance.inl"))?;
+/// Write keymap.c and petkau_tap_dance.inl from Oryx's keymap.c, returning the macro sets its layers use.
+pub(crate) fn update_keymap_c(config: &Config, input: &str) -> Result<BTreeSet<MacroSet>> {
+    info!("Updating keymap.c...");
+    let export_folder = config.export_folder();
+    let keymap_c = &mut TrackedFile::create(&export_folder, "keymap.c")?;
+    let petkau_tap_dance_inl = &mut TrackedFile::create(&export_folder, "petkau_tap_dance.inl")?;
+    keymap_c.set_origin(Stage::OryxPassthrough, "preprocessing");
+    petkau_tap_dance_inl.set_origin(Stage::TapDance, "tap_dance_enum");
+    let dance = Regex::new(r"dance_(\d+)")?;
 
     // keymap.c has the following format:
     // - preprocessing
@@ -47,12 +56,13 @@ pub(crate) fn update_keymap_c() -> Result<()> {
     let mut input_section = KeymapSection::Prepocessing;
     let mut input_macro_defs = String::new();
     let mut keymap = String::new();
-    for line in io::BufReader::new(input).lines() {
-        let line = line?;
+    for line in input.lines() {
         match input_section {
             KeymapSection::Prepocessing => {
                 if line == "enum custom_keycodes {" {
+                    keymap_c.set_origin(Stage::MacroTranslation, "petkau_macros.inl");
                     writeln!(keymap_c, "#include \"petkau_macros.inl\"")?;
+                    keymap_c.set_origin(Stage::OryxPassthrough, "rgb_setup");
                     input_section = KeymapSection::MacroEnum;
                 } else {
                     writeln!(keymap_c, "{line}")?;
@@ -66,7 +76,7 @@ pub(crate) fn update_keymap_c() -> Result<()> {
             }
             KeymapSection::TapDanceEnum => {
                 if line == "const uint16_t PROGMEM keymaps[][MATRIX_ROWS][MATRIX_COLS] = {" {
-                    keymap.push_str(&line);
+                    keymap.push_str(line);
                     keymap.push('\n');
                     input_section = KeymapSection::Keymap;
                 } else {
@@ -74,7 +84,7 @@ pub(crate) fn update_keymap_c() -> Result<()> {
                 }
             }
             KeymapSection::Keymap => {
-                keymap.push_str(&line);
+                keymap.push_str(line);
                 keymap.push('\n');
                 if line == "extern rgb_config_t rgb_matrix_config;" {
                     input_section = KeymapSection::RGBSetup;
@@ -82,7 +92,7 @@ pub(crate) fn update_keymap_c() -> Result<()> {
             }
             KeymapSection::RGBSetup => {
                 if line == "bool process_record_user(uint16_t keycode, keyrecord_t *record) {" {
-                    input_macro_defs.push_str(&line);
+                    input_macro_defs.push_str(line);
                     input_macro_defs.push('\n');
                     input_section = KeymapSection::MacroDefs;
                 } else {
@@ -91,10 +101,11 @@ pub(crate) fn update_keymap_c() -> Result<()> {
             }
             KeymapSection::MacroDefs => {
                 if line == "typedef struct {" {
-                     writeln!(keymap_c, "{line}")?;
+                    keymap_c.set_origin(Stage::TapDance, "tap_dance_setup");
+                    writeln!(keymap_c, "{line}")?;
                     input_section = KeymapSection::TapDanceSetup;
                 } else {
-                    input_macro_defs.push_str(&line);
+                    input_macro_defs.push_str(line);
                     input_macro_defs.push('\n');
                 }
             }
@@ -107,27 +118,67 @@ pub(crate) fn update_keymap_c() -> Result<()> {
                 }
             }
             // Overwrite petkau_tap_dance.inl with tap_dance_defs.
-            KeymapSection::TapDanceDefs => writeln!(petkau_tap_dance_inl, "{line}")?,
+            KeymapSection::TapDanceDefs => {
+                if let Some(captures) = dance.captures(line) {
+                    petkau_tap_dance_inl
+                        .set_origin(Stage::TapDance, format!("Oryx DANCE_{}", &captures[1]));
+                }
+                writeln!(petkau_tap_dance_inl, "{line}")?
+            }
         }
     }
+    keymap_c.set_origin(Stage::Override, "petkau_*.inl includes");
     writeln!(keymap_c, "#include \"petkau_tapping_term.inl\"")?;
     writeln!(keymap_c, "#include \"petkau_tap_dance.inl\"")?;
     writeln!(keymap_c, "#include \"process_record_petkau.inl\"")?;
 
-    let macro_code_translations = build_macro_code_translator(&input_macro_defs)?;
-
-    let custom_keycodes = macro_code_translations
+    // Translate the macro codes against the macro set of every layer, since a macro may be bound on several, and
+    // against the default set if any is bound outside the layers, e.g. in a combo.
+    let layout = Regex::new(r"\[(\d+)\][[:space:]]*=[[:space:]]*LAYOUT")?;
+    let st_macro = Regex::new(r"ST_MACRO_(\d+)")?;
+    let layers = layout
+        .captures_iter(&keymap)
+        .map(|captures| Ok(captures[1].parse::<usize>()?))
+        .collect::<Result<BTreeSet<usize>>>()?;
+    let mut macro_sets = macro_sets(config, &layers);
+    if has_macros_outside_layers(&keymap, &layout, &st_macro) {
+        macro_sets.insert(config.macro_set);
+    }
+    let macro_code_translations = macro_sets
         .iter()
-        .enumerate()
-        .filter_map(|(i, macro_code)| {
-            if macro_code.is_none() {
-                Some(format!("\tST_MACRO_{i}"))
-            } else {
-                None
-            }
-        })
+        .copied()
+        .map(|set| Ok((set, build_macro_code_translator(&input_macro_defs, set)?)))
+        .collect::<Result<BTreeMap<_, _>>>()?;
+    for (set, translations) in &macro_code_translations {
+        for (i, translation) in translations.iter().enumerate() {
+            report::macro_translation(
+                i,
+                set.name(),
+                translation.code.as_deref(),
+                translation
+                    .candidates
+                    .iter()
+                    .copied()
+                    .map(String::from)
+                    .collect(),
+                translation.chosen.map(String::from),
+            );
+        }
+    }
+    // A macro keeps its definition while any layer's macro set leaves it untranslated.
+    let untranslated = |i: usize| {
+        macro_code_translations
+            .values()
+            .any(|translations| translations[i].chosen.is_none())
+    };
+    let macro_count = macro_code_translations.values().next().map_or(0, Vec::len);
+
+    let custom_keycodes = (0..macro_count)
+        .filter(|&i| untranslated(i))
+        .map(|i| format!("\tST_MACRO_{i}"))
         .collect::<Vec<String>>();
     if !custom_keycodes.is_empty() {
+        keymap_c.set_origin(Stage::MacroTranslation, "untranslated ST_MACRO_# keycodes");
         writeln!(keymap_c)?;
         writeln!(keymap_c, "enum custom_keycodes")?;
         writeln!(keymap_c, "{{")?;
@@ -142,43 +193,192 @@ pub(crate) fn update_keymap_c() -> Result<()> {
     )?;
     let macro_defs = cases.replace_all(&input_macro_defs, |captures: &Captures| {
         let i = captures[1].parse::<usize>().unwrap();
-        macro_code_translations[i].map_or(captures[0].to_string(), |_| String::new())
+        if untranslated(i) {
+            captures[0].to_string()
+        } else {
+            String::new()
+        }
     });
     let macro_defs = Regex::new("(?s:case RGB_SLD:(?:.+)return false;\n)")?.replace(
         &macro_defs,
         "default: return process_record_petkau(keycode, record);\n",
     );
+    keymap_c.set_origin(
+        Stage::MacroTranslation,
+        "untranslated ST_MACRO_# definitions",
+    );
     writeln!(keymap_c)?;
     write!(keymap_c, "{macro_defs}")?;
 
-    // Write the keymap with "petkau" macros installed.
-    let keymap = Regex::new(r"ST_MACRO_(\d+)")?.replace_all(&keymap, |captures: &Captures| {
-        let i = captures[1].parse::<usize>().unwrap();
-        match macro_code_translations[i]{
-            Some(macro_code_translation) => match macro_code_translation{
-                MacroCode::Macro(petkau_macro) => format!("PETKAU_MACRO_{:?}", petkau_macro),
-                MacroCode::CustomKeycode(custom_keycode) => String::from(custom_keycode),
-            },
-            None => 
-            captures[0].to_string(),
+    // Write the keymap with "petkau" macros installed, attributing each line to its Oryx key positions.
+    let mut layer = None;
+    let mut key = 0;
+    for line in keymap.lines() {
+        let mut translations = Vec::new();
+        let mut undefined = None;
+        let line = st_macro.replace_all(line, |captures: &Captures| {
+            let i = captures[1].parse::<usize>().unwrap();
+            let set = layer.map_or(config.macro_set, |layer| config.macro_set(layer));
+            let translation = macro_code_translations
+                .get(&set)
+                .and_then(|translations| translations.get(i));
+            match translation.and_then(|translation| translation.chosen) {
+                Some(macro_code_translation) => {
+                    let translation = String::from(macro_code_translation);
+                    translations.push(format!("{} -> {translation}", &captures[0]));
+                    translation
+                }
+                None => {
+                    if translation.is_none() {
+                        undefined = Some(captures[0].to_string());
+                    }
+                    captures[0].to_string()
+                }
+            }
+        });
+        if let Some(keycode) = undefined {
+            return Err(anyhow!(
+                "keymap.c binds {keycode}, which it doesn't define."
+            ));
         }
-    });
-    write!(keymap_c, "{keymap}")?;
+        if let Some(captures) = layout.captures(&line) {
+            layer = Some(captures[1].parse::<usize>()?);
+            key = 0;
+            keymap_c.set_origin(Stage::OryxPassthrough, format!("layer {}", &captures[1]));
+        } else if line.trim_start().starts_with(')') {
+            if let Some(layer) = layer.take() {
+                keymap_c.set_origin(Stage::OryxPassthrough, format!("layer {layer}"));
+            }
+        } else if let Some(layer) = &layer {
+            let keys = count_keys(&line);
+            let detail = format!("layer {layer}, keys {key}-{}", key + keys.max(1) - 1);
+            key += keys;
+            if translations.is_empty() {
+                keymap_c.set_origin(Stage::OryxPassthrough, detail);
+            } else {
+                keymap_c.set_origin(
+                    Stage::MacroTranslation,
+                    format!("{detail}: {}", translations.join(", ")),
+                );
+            }
+        } else {
+            keymap_c.set_origin(Stage::OryxPassthrough, "keymaps");
+        }
+        writeln!(keymap_c, "{line}")?;
+    }
 
-    println!("done.");
-    Ok(())
+    info!("done.");
+    Ok(macro_sets)
 }
 
-#[derive(Clone, Copy, Debug)]
-enum MacroCode {
+/// Whether any ST_MACRO_# is bound outside every layer's LAYOUT_*(...), e.g. in a combo. Such macros resolve against
+/// the default macro set.
+fn has_macros_outside_layers(keymap: &str, layout: &Regex, st_macro: &Regex) -> bool {
+    let mut in_layer = false;
+    keymap.lines().any(|line| {
+        let outside = !in_layer && st_macro.is_match(line);
+        if layout.is_match(line) {
+            in_layer = true;
+        } else if line.trim_start().starts_with(')') {
+            in_layer = false;
+        }
+        outside
+    })
+}
+
+/// The macro sets the layers' macro codes resolve against, or just the default set if there are no layers. Warns
+/// about MACRO_SET.<layer> settings for layers the export doesn't have.
+pub(crate) fn macro_sets(config: &Config, layers: &BTreeSet<usize>) -> BTreeSet<MacroSet> {
+    for layer in config
+        .macro_sets
+        .keys()
+        .filter(|layer| !layers.contains(layer))
+    {
+        warning!("Warning: MACRO_SET.{layer} is set, but the export has no layer {layer}.");
+    }
+    let mut sets = layers
+        .iter()
+        .map(|&layer| config.macro_set(layer))
+        .collect::<BTreeSet<_>>();
+    if sets.is_empty() {
+        sets.insert(config.macro_set);
+    }
+    sets
+}
+
+/// Count the comma-separated keycodes on one line of a LAYOUT_*(...), ignoring commas within e.g. MT(MOD_LSFT, KC_A).
+fn count_keys(line: &str) -> usize {
+    let mut depth = 0;
+    let mut keys = 0;
+    let mut in_key = false;
+    for c in line.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => in_key = false,
+            _ if !c.is_whitespace() && !in_key => {
+                in_key = true;
+                keys += 1;
+            }
+            _ => {}
+        }
+    }
+    keys
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum MacroCode {
     Macro(Macro),
     CustomKeycode(CustomKeycode),
 }
 
-/// Map macro indices (i.e. the # in ST_MACRO_#) to the corresponding MacroCode enum value (which may be None).
-fn build_macro_code_translator(
-    input_macro_defs: &str,
-) -> Result<Vec<Option<MacroCode>>, anyhow::Error> {
+impl MacroCode {
+    /// Every macro, then every custom keycode.
+    pub(crate) fn all() -> impl Iterator<Item = MacroCode> {
+        all::<Macro>()
+            .map(MacroCode::Macro)
+            .chain(all::<CustomKeycode>().map(MacroCode::CustomKeycode))
+    }
+
+    /// Whether the macro code belongs to the set. Custom keycodes belong to every set.
+    pub(crate) fn in_set(self, set: MacroSet) -> bool {
+        match self {
+            MacroCode::Macro(value) => value.sets().contains(&set),
+            MacroCode::CustomKeycode(_) => true,
+        }
+    }
+
+    /// The text a macro code abbreviates, e.g. "return" or "DT_UP".
+    pub(crate) fn text(self) -> String {
+        match self {
+            MacroCode::Macro(value) => String::from(value),
+            MacroCode::CustomKeycode(value) => String::from(value),
+        }
+    }
+}
+
+/// What rover makes of one ST_MACRO_#.
+#[derive(Clone)]
+pub(crate) struct MacroTranslation {
+    /// The text the macro types, if it's a SEND_STRING of plain taps.
+    pub(crate) code: Option<String>,
+    /// Every macro code whose text starts with `code`.
+    pub(crate) candidates: Vec<MacroCode>,
+    /// The macro code replacing the macro, or none to keep it as Oryx wrote it.
+    pub(crate) chosen: Option<MacroCode>,
+}
+
+impl From<MacroCode> for String {
+    fn from(macro_code: MacroCode) -> Self {
+        match macro_code {
+            MacroCode::Macro(petkau_macro) => format!("PETKAU_MACRO_{:?}", petkau_macro),
+            MacroCode::CustomKeycode(custom_keycode) => String::from(custom_keycode),
+        }
+    }
+}
+
+/// Decode the contents of a SEND_STRING(...) of SS_TAP(...)s back into the text it types.
+pub(crate) fn decode_send_string(send_string: &str) -> Result<String> {
     let tap = "SS_TAP\\(X_([[:alnum:]]+)\\)";
     let shift = "SS_(?:L|R)SFT";
     let shift_tap = &format!("{shift}\\({tap}\\)");
@@ -188,40 +388,50 @@ fn build_macro_code_translator(
     let control_taps = Regex::new(control_tap)?;
     let shift_taps = Regex::new(shift_tap)?;
     let all_taps = Regex::new(&format!("{control_tap}|{shift_tap}|{tap}"))?;
-    Ok(Regex::new(r"SEND_STRING\((.+)\);\n")?
+    all_taps
+        .captures_iter(send_string)
+        .map(|tap| {
+            let full_text = &tap[0];
+            if control_taps.is_match(full_text) {
+                return Err(anyhow!("Macro uses Ctrl."));
+            }
+            let shifted = shift_taps.is_match(full_text);
+            let qmk_name = if shifted {
+                shift_taps.captures(full_text).unwrap()[1].to_string()
+            } else {
+                taps.captures(full_text).unwrap()[1].to_string()
+            };
+            qmk_name::to_char(&qmk_name, shifted)
+        })
+        .collect::<Result<String>>()
+}
+
+/// Map macro indices (i.e. the # in ST_MACRO_#) to the corresponding MacroCode enum value (which may be None),
+/// matching only the macros of the given set. The macro defs may be all of keymap.c, since Oryx only uses
+/// SEND_STRING in them.
+pub(crate) fn build_macro_code_translator(
+    input_macro_defs: &str,
+    set: MacroSet,
+) -> Result<Vec<MacroTranslation>, anyhow::Error> {
+    Ok(Regex::new(r"SEND_STRING\((.+)\);\r?\n")?
         .captures_iter(input_macro_defs)
         .map(|send_string| {
-            all_taps
-                .captures_iter(&send_string[1])
-                .map(|tap| {
-                    let full_text = &tap[0];
-                    if control_taps.is_match(full_text) {
-                        return Err(anyhow!("Macro uses Ctrl."));
-                    }
-                    let shifted = shift_taps.is_match(full_text);
-                    let qmk_name = if shifted {
-                        shift_taps.captures(full_text).unwrap()[1].to_string()
-                    } else {
-                        taps.captures(full_text).unwrap()[1].to_string()
-                    };
-                    qmk_name::to_char(&qmk_name, shifted)
-                })
-                .collect::<Result<String>>().ok()
-                .and_then(|macro_code| {
+            let macro_code = decode_send_string(&send_string[1]).ok();
+            let translation = macro_code.as_deref().and_then(|macro_code| {
                     let mut matching_macros =
-                        all::<Macro>().filter(|&value| String::from(value).starts_with(&macro_code));
+                        all::<Macro>().filter(|&value| value.sets().contains(&set) && String::from(value).starts_with(macro_code));
                     let macro_code_translation = match matching_macros.clone().count() {
                         0 => {
-                            println!("No macro matches macro code '{macro_code}'. Try to match custom keys next.");
+                            debug!("No macro matches macro code '{macro_code}'. Try to match custom keys next.");
                             None
                         }
                         1 => {
                             let matching_macro = matching_macros.next().unwrap();
-                            println!("Matched macro code '{macro_code}' to macro '{matching_macro:?}'.");
+                            detail!("Matched macro code '{macro_code}' to macro '{matching_macro:?}'.");
                             Some(MacroCode::Macro(matching_macro))}
                         _ => {
                             let first = matching_macros.next().unwrap();
-                            println!(
+                            warning!(
                                 "Multiple macro matches for '{macro_code}': {:?}. Using the first match '{}'.",
                                 matching_macros.collect::<Vec<_>>(),String::from(first)
                             );
@@ -230,20 +440,20 @@ fn build_macro_code_translator(
                     };
                     if macro_code_translation.is_none() {                        
                         let mut matching_custom_keycodes =
-                            all::<CustomKeycode>().filter(|&value| String::from(value).starts_with(&macro_code));
+                            all::<CustomKeycode>().filter(|&value| String::from(value).starts_with(macro_code));
                         match matching_custom_keycodes.clone().count() {
                             0 => {
-                                println!("No custom key matches macro code '{macro_code}'. Using it literally.");
+                                detail!("No custom key matches macro code '{macro_code}'. Using it literally.");
                                 None
                             }
                             1 => {
                                 let custom_keycode =matching_custom_keycodes.next().unwrap();
-                                println!("Matched macro code '{macro_code}' to custom keycode '{custom_keycode:?}'.");
+                                detail!("Matched macro code '{macro_code}' to custom keycode '{custom_keycode:?}'.");
                                 Some(MacroCode::CustomKeycode( custom_keycode ))
                             },
                             _ => {
                                 let first = matching_custom_keycodes.next().unwrap();
-                                println!(
+                                warning!(
                                     "Multiple custom key matches for '{macro_code}': {:?}. Using the first match '{}'.",
                                     matching_custom_keycodes.collect::<Vec<_>>(),String::from(first)
                                 );
@@ -253,7 +463,112 @@ fn build_macro_code_translator(
                     } else {
                         macro_code_translation
                     }
-            })
+            });
+            let candidates = macro_code
+                .as_deref()
+                .map_or_else(Vec::new, |macro_code| candidates(macro_code, set));
+            MacroTranslation {
+                code: macro_code,
+                candidates,
+                chosen: translation,
+            }
         })
         .collect::<Vec<_>>())
 }
+
+/// Every macro of the set, then every custom keycode, whose text starts with the macro code.
+fn candidates(macro_code: &str, set: MacroSet) -> Vec<MacroCode> {
+    MacroCode::all()
+        .filter(|candidate| candidate.in_set(set) && candidate.text().starts_with(macro_code))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::OryxExport;
+    use std::fs;
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/moonlander_source"
+    );
+
+    fn chosen(input: &str, set: MacroSet) -> Vec<Option<String>> {
+        let export = OryxExport::read(input).unwrap();
+        build_macro_code_translator(&export.keymap_c, set)
+            .unwrap()
+            .into_iter()
+            .map(|translation| translation.chosen.map(String::from))
+            .collect()
+    }
+
+    #[test]
+    fn translates_macros_of_extracted_folder() {
+        assert_eq!(
+            chosen(FIXTURE, MacroSet::Cpp),
+            [
+                Some("PETKAU_MACRO_Return".to_string()),
+                Some("PETKAU_MACRO_NullPtr".to_string()),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn translates_macros_of_lone_keymap_c() {
+        let input = format!("{FIXTURE}/keymap.c");
+        assert_eq!(
+            chosen(&input, MacroSet::Cpp),
+            chosen(FIXTURE, MacroSet::Cpp)
+        );
+    }
+
+    #[test]
+    fn translates_macros_with_crlf_line_endings() {
+        let export = OryxExport::read(FIXTURE).unwrap();
+        let keymap_c = export.keymap_c.replace('\n', "\r\n");
+        let translations = build_macro_code_translator(&keymap_c, MacroSet::Cpp).unwrap();
+        assert_eq!(
+            translations
+                .into_iter()
+                .map(|translation| translation.chosen.map(String::from))
+                .collect::<Vec<_>>(),
+            chosen(FIXTURE, MacroSet::Cpp)
+        );
+    }
+
+    #[test]
+    fn translates_macros_outside_layers_against_default_set() {
+        let qmk_firmware = std::env::temp_dir().join("rover-keymap-combo");
+        let _ = fs::remove_dir_all(&qmk_firmware);
+        let config = Config {
+            qmk_firmware,
+            macro_sets: BTreeMap::from([(0, MacroSet::Rust), (1, MacroSet::Rust)]),
+            ..Config::default()
+        };
+        fs::create_dir_all(config.export_folder()).unwrap();
+        let export = OryxExport::read(FIXTURE).unwrap();
+        let input = export.keymap_c.replace(
+            "extern rgb_config_t",
+            "combo_t key_combos[] = { COMBO(combo0, ST_MACRO_0) };\n\nextern rgb_config_t",
+        );
+
+        let sets = update_keymap_c(&config, &input).unwrap();
+        assert_eq!(sets, BTreeSet::from([MacroSet::Cpp, MacroSet::Rust]));
+        let keymap_c =
+            fs::read_to_string(config.export_folder().join("keymap.c.rover-new")).unwrap();
+        assert!(keymap_c.contains("COMBO(combo0, PETKAU_MACRO_Return)"));
+        let _ = fs::remove_dir_all(&config.qmk_firmware);
+    }
+
+    #[test]
+    fn translates_against_macro_set() {
+        // Python has no nullptr, so "null" stays as Oryx wrote it.
+        assert_eq!(
+            chosen(FIXTURE, MacroSet::Python),
+            [Some("PETKAU_MACRO_Return".to_string()), None, None, None]
+        );
+    }
+}
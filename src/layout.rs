@@ -0,0 +1,156 @@
+use crate::{keymap, source::OryxExport};
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// The parts of an Oryx source export that matter to a human reading a diff.
+pub(crate) struct Layout {
+    /// Keycodes of every key, per layer, in LAYOUT_* argument order.
+    pub(crate) layers: BTreeMap<usize, Vec<String>>,
+    /// Decoded text of each ST_MACRO_#, keyed by #.
+    pub(crate) macros: BTreeMap<usize, String>,
+    /// Actions of each DANCE_#, keyed by # and then by tap dance step (e.g. SINGLE_TAP).
+    pub(crate) tap_dances: BTreeMap<usize, BTreeMap<String, String>>,
+    /// #defines from config.h.
+    pub(crate) config: BTreeMap<String, String>,
+    /// Variable assignments from rules.mk.
+    pub(crate) rules: BTreeMap<String, String>,
+}
+
+impl Layout {
+    /// Parse the keymap.c, config.h and rules.mk of the given downloaded .zip file, folder or keymap.c.
+    pub(crate) fn read(input: &str) -> Result<Self> {
+        Self::of(&OryxExport::read(input)?)
+    }
+
+    pub(crate) fn of(export: &OryxExport) -> Result<Self> {
+        Self::parse(&export.keymap_c, &export.config_h, &export.rules_mk)
+    }
+
+    pub(crate) fn parse(keymap_c: &str, config_h: &str, rules_mk: &str) -> Result<Self> {
+        Ok(Self {
+            layers: parse_layers(keymap_c)?,
+            macros: parse_macros(keymap_c)?,
+            tap_dances: parse_tap_dances(keymap_c)?,
+            config: parse_config(config_h)?,
+            rules: parse_rules(rules_mk),
+        })
+    }
+
+    /// The Oryx layout and revision ids from the FIRMWARE_VERSION in config.h, e.g. u8"aBcDe/xyz12".
+    pub(crate) fn revision(&self) -> Option<(&str, &str)> {
+        self.config
+            .get("FIRMWARE_VERSION")?
+            .trim_start_matches("u8")
+            .trim_matches('"')
+            .split_once('/')
+    }
+
+    /// Describe a keycode in human terms, e.g. ST_MACRO_3 becomes ST_MACRO_3 ("retur").
+    pub(crate) fn describe_keycode(&self, keycode: &str) -> String {
+        let macro_index = keycode
+            .strip_prefix("ST_MACRO_")
+            .and_then(|i| i.parse::<usize>().ok());
+        match macro_index.and_then(|i| self.macros.get(&i)) {
+            Some(text) => format!("{keycode} (\"{text}\")"),
+            None => keycode.to_string(),
+        }
+    }
+}
+
+/// Parse each "[#] = LAYOUT_*(...)" entry of the keymaps array.
+fn parse_layers(keymap_c: &str) -> Result<BTreeMap<usize, Vec<String>>> {
+    let start = keymap_c
+        .find("keymaps[][MATRIX_ROWS][MATRIX_COLS] = {")
+        .ok_or_else(|| anyhow!("keymap.c has no keymaps array."))?;
+    let keymaps = &keymap_c[start..];
+    let mut layers = BTreeMap::new();
+    for captures in
+        Regex::new(r"\[(\d+)\][[:space:]]*=[[:space:]]*LAYOUT\w*\(")?.captures_iter(keymaps)
+    {
+        let index = captures[1].parse::<usize>()?;
+        let arguments = &keymaps[captures.get(0).unwrap().end()..];
+        layers.insert(index, split_arguments(arguments)?);
+    }
+    Ok(layers)
+}
+
+/// Split the comma-separated arguments up to the closing parenthesis, respecting nested calls
+/// like MT(MOD_LSFT, KC_A).
+fn split_arguments(arguments: &str) -> Result<Vec<String>> {
+    let mut depth = 0;
+    let mut current = String::new();
+    let mut keys = Vec::new();
+    for c in arguments.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                keys.push(current.trim().to_string());
+                return Ok(keys);
+            }
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                keys.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        if !c.is_whitespace() {
+            current.push(c);
+        }
+    }
+    Err(anyhow!("Unterminated LAYOUT in keymap.c."))
+}
+
+fn parse_macros(keymap_c: &str) -> Result<BTreeMap<usize, String>> {
+    Regex::new(
+        r"case ST_MACRO_(\d+):[[:space:]]+if \(record->event\.pressed\) \{[[:space:]]+SEND_STRING\((.+)\);",
+    )?
+    .captures_iter(keymap_c)
+    .map(|captures| {
+        let text = keymap::decode_send_string(&captures[2]).unwrap_or_else(|_| captures[2].to_string());
+        Ok((captures[1].parse::<usize>()?, text))
+    })
+    .collect()
+}
+
+/// Parse the steps of each "dance_#_finished" function, e.g. "case SINGLE_TAP: register_code16(KC_A); break;".
+fn parse_tap_dances(keymap_c: &str) -> Result<BTreeMap<usize, BTreeMap<String, String>>> {
+    let step = Regex::new(r"case (\w+):(.*)")?;
+    let action = Regex::new(r"(?:register_code16|tap_code16)\(([^)]+)\)")?;
+    Regex::new(r"(?s)void dance_(\d+)_finished\(.*?\n\}")?
+        .captures_iter(keymap_c)
+        .map(|dance| {
+            let steps = step
+                .captures_iter(&dance[0])
+                .map(|step| {
+                    let actions = action
+                        .captures_iter(&step[2])
+                        .map(|action| action[1].to_string())
+                        .collect::<Vec<_>>();
+                    (step[1].to_string(), actions.join(" "))
+                })
+                .collect();
+            Ok((dance[1].parse::<usize>()?, steps))
+        })
+        .collect()
+}
+
+fn parse_config(config_h: &str) -> Result<BTreeMap<String, String>> {
+    let define = Regex::new(r"^#define[[:space:]]+(\w+)(.*)$")?;
+    Ok(config_h
+        .lines()
+        .filter_map(|line| define.captures(line))
+        .map(|captures| (captures[1].to_string(), captures[2].trim().to_string()))
+        .collect())
+}
+
+fn parse_rules(rules_mk: &str) -> BTreeMap<String, String> {
+    rules_mk
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
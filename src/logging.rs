@@ -0,0 +1,199 @@
+use crate::report;
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    io::{self, Write},
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+/// How important a message is. Each -v shows one more level; -q shows only warnings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Level {
+    Warn,
+    #[default]
+    Info,
+    /// Decisions rover made, e.g. how it translated each macro, and how long each stage took.
+    Detail,
+    Debug,
+}
+
+impl Level {
+    /// The next level up, for each -v.
+    pub(crate) fn louder(self) -> Self {
+        match self {
+            Level::Warn => Level::Info,
+            Level::Info => Level::Detail,
+            Level::Detail | Level::Debug => Level::Debug,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Detail => "detail",
+            Level::Debug => "debug",
+        }
+    }
+}
+
+struct Logger {
+    verbosity: Level,
+    start: Instant,
+    /// Gets one JSON object per line for each message and stage, if asked for with --log-json.
+    json: Option<Mutex<fs::File>>,
+    /// Text printed so far of the current line, so the JSON log gets whole lines.
+    partial_line: Mutex<String>,
+    /// Stages currently running, innermost last.
+    stages: Mutex<Vec<&'static str>>,
+}
+
+impl Logger {
+    fn new(verbosity: Level, json: Option<fs::File>) -> Self {
+        Self {
+            verbosity,
+            start: Instant::now(),
+            json: json.map(Mutex::new),
+            partial_line: Default::default(),
+            stages: Default::default(),
+        }
+    }
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+fn logger() -> &'static Logger {
+    LOGGER.get_or_init(|| Logger::new(Level::default(), None))
+}
+
+/// Set how much to print and where to write the JSON log. Must come before anything is logged.
+pub(crate) fn init(verbosity: Level, json: Option<&str>) -> Result<()> {
+    let json = match json {
+        Some(path) => Some(
+            fs::File::create(path).map_err(|error| anyhow!("Can't create '{path}': {error}"))?,
+        ),
+        None => None,
+    };
+    LOGGER
+        .set(Logger::new(verbosity, json))
+        .map_err(|_| anyhow!("Logging initialized after first use."))
+}
+
+/// Whether messages of the given level are printed.
+pub(crate) fn shows(level: Level) -> bool {
+    level <= logger().verbosity
+}
+
+/// Print the text if its level is shown, and add it to the JSON log. Without `end_line`, the text is the start of
+/// a line finished by later calls, like print! rather than println!.
+pub(crate) fn write(level: Level, text: &str, end_line: bool) {
+    if shows(level) {
+        if end_line {
+            println!("{text}");
+        } else {
+            print!("{text}");
+            let _ = io::stdout().flush();
+        }
+    }
+    let Ok(mut partial_line) = logger().partial_line.lock() else {
+        return;
+    };
+    partial_line.push_str(text);
+    if end_line {
+        let line = std::mem::take(&mut *partial_line);
+        record(level, &line);
+    }
+}
+
+/// Add a line to the JSON log only, e.g. one already shown some other way.
+pub(crate) fn record(level: Level, text: &str) {
+    write_json(json!({
+        "level": level.name(),
+        "stage": current_stage(),
+        "message": text,
+    }));
+}
+
+fn current_stage() -> Option<&'static str> {
+    logger().stages.lock().ok()?.last().copied()
+}
+
+fn write_json(mut entry: Value) {
+    let logger = logger();
+    let Some(json) = &logger.json else {
+        return;
+    };
+    entry["elapsed_ms"] = json!(logger.start.elapsed().as_millis() as u64);
+    if let Ok(mut json) = json.lock() {
+        let _ = writeln!(json, "{entry}");
+    }
+}
+
+/// Run a stage of the pipeline, attributing what it logs to it and logging how long it took and whether it
+/// succeeded.
+pub(crate) fn stage<T>(name: &'static str, run: impl FnOnce() -> Result<T>) -> Result<T> {
+    if let Ok(mut stages) = logger().stages.lock() {
+        stages.push(name);
+    }
+    let start = Instant::now();
+    let result = run();
+    let duration = start.elapsed();
+    let status = if result.is_ok() { "ok" } else { "failed" };
+    if shows(Level::Detail) {
+        println!("Stage '{name}' {status} after {}.", seconds(duration));
+    }
+    write_json(json!({
+        "level": Level::Detail.name(),
+        "stage": name,
+        "status": status,
+        "duration_ms": duration.as_millis() as u64,
+    }));
+    report::stage(name, status, duration);
+    if let Ok(mut stages) = logger().stages.lock() {
+        stages.pop();
+    }
+    result
+}
+
+fn seconds(duration: Duration) -> String {
+    format!("{:.2}s", duration.as_secs_f64())
+}
+
+/// Like print!, at Info level.
+macro_rules! progress {
+    ($($arg:tt)*) => {
+        $crate::logging::write($crate::logging::Level::Info, &format!($($arg)*), false)
+    };
+}
+
+/// Like println!, at Info level.
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::logging::write($crate::logging::Level::Info, &format!($($arg)*), true)
+    };
+}
+
+/// Like println!, at Warn level, so shown even with -q.
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        $crate::logging::write($crate::logging::Level::Warn, &format!($($arg)*), true)
+    };
+}
+
+/// Like println!, at Detail level, so shown with -v.
+macro_rules! detail {
+    ($($arg:tt)*) => {
+        $crate::logging::write($crate::logging::Level::Detail, &format!($($arg)*), true)
+    };
+}
+
+/// Like println!, at Debug level, so shown with -vv.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::logging::write($crate::logging::Level::Debug, &format!($($arg)*), true)
+    };
+}
+
+pub(crate) use {debug, detail, info, progress, warning};
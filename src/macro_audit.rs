@@ -0,0 +1,182 @@
+use crate::{
+    args::Args,
+    config::Config,
+    keymap::{self, MacroCode, MacroTranslation},
+    layout::Layout,
+    macros::MacroSet,
+    select,
+    source::OryxExport,
+};
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::{collections::BTreeMap, fmt};
+
+/// An ST_MACRO_# of an Oryx export, what rover makes of it against one macro set and where it's bound on layers
+/// using that set.
+pub(crate) struct AuditedMacro {
+    pub(crate) index: usize,
+    pub(crate) set: MacroSet,
+    pub(crate) translation: MacroTranslation,
+    /// (layer, key) of every such key bound to it, in LAYOUT_* argument order.
+    pub(crate) keys: Vec<(usize, usize)>,
+}
+
+/// How the macros of an Oryx export map onto the macro dictionary.
+pub(crate) struct MacroAudit {
+    pub(crate) macros: Vec<AuditedMacro>,
+    /// Dictionary entries of the layers' macro sets no key types, either directly or through a translated
+    /// ST_MACRO_#.
+    pub(crate) unreferenced: Vec<MacroCode>,
+}
+
+impl MacroAudit {
+    pub(crate) fn new(config: &Config, export: &OryxExport) -> Result<Self> {
+        let layout = Layout::of(export)?;
+        let st_macro = Regex::new(r"ST_MACRO_(\d+)\b")?;
+        let layers = layout.layers.keys().copied().collect();
+        let sets = keymap::macro_sets(config, &layers);
+        let translations = sets
+            .iter()
+            .copied()
+            .chain([config.macro_set])
+            .map(|set| {
+                Ok((
+                    set,
+                    keymap::build_macro_code_translator(&export.keymap_c, set)?,
+                ))
+            })
+            .collect::<Result<BTreeMap<_, _>>>()?;
+
+        // The keys bound to each macro, by macro and the macro set of the keys' layer.
+        let mut bindings = BTreeMap::<(usize, MacroSet), Vec<(usize, usize)>>::new();
+        let mut referenced = Vec::new();
+        for (&layer, keycodes) in &layout.layers {
+            let set = config.macro_set(layer);
+            for (key, keycode) in keycodes.iter().enumerate() {
+                referenced.extend(MacroCode::all().filter(|&code| String::from(code) == *keycode));
+                for captures in st_macro.captures_iter(keycode) {
+                    let index = captures[1].parse::<usize>()?;
+                    let translation = translations[&set].get(index).ok_or_else(|| {
+                        anyhow!("Layer {layer} key {key} has undefined {}.", &captures[0])
+                    })?;
+                    referenced.extend(translation.chosen);
+                    bindings.entry((index, set)).or_default().push((layer, key));
+                }
+            }
+        }
+
+        // List each macro once per macro set it's bound with, or once with the default set if it's unbound.
+        let mut macros = Vec::new();
+        for (index, translation) in translations[&config.macro_set].iter().enumerate() {
+            let bound = bindings
+                .iter()
+                .filter(|((i, _), _)| *i == index)
+                .map(|(&(_, set), keys)| AuditedMacro {
+                    index,
+                    set,
+                    translation: translations[&set][index].clone(),
+                    keys: keys.clone(),
+                })
+                .collect::<Vec<_>>();
+            if bound.is_empty() {
+                macros.push(AuditedMacro {
+                    index,
+                    set: config.macro_set,
+                    translation: translation.clone(),
+                    keys: Vec::new(),
+                });
+            }
+            macros.extend(bound);
+        }
+        Ok(Self {
+            macros,
+            unreferenced: MacroCode::all()
+                .filter(|&code| sets.iter().any(|&set| code.in_set(set)))
+                .filter(|code| !referenced.contains(code))
+                .collect(),
+        })
+    }
+}
+
+/// e.g. PETKAU_MACRO_Return ("return"), or just DT_UP for a custom keycode.
+fn describe(code: MacroCode) -> String {
+    match code {
+        MacroCode::Macro(_) => format!("{} (\"{}\")", String::from(code), code.text()),
+        MacroCode::CustomKeycode(_) => String::from(code),
+    }
+}
+
+impl fmt::Display for AuditedMacro {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let translation = &self.translation;
+        match &translation.code {
+            Some(code) => writeln!(
+                f,
+                "ST_MACRO_{} types \"{code}\" ({} macros).",
+                self.index,
+                self.set.name()
+            )?,
+            None => writeln!(
+                f,
+                "ST_MACRO_{} isn't plain taps ({} macros).",
+                self.index,
+                self.set.name()
+            )?,
+        }
+        let candidates = translation
+            .candidates
+            .iter()
+            .map(|&code| describe(code))
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            writeln!(f, "\tCandidates: none")?;
+        } else {
+            writeln!(f, "\tCandidates: {}", candidates.join(", "))?;
+        }
+        match translation.chosen {
+            Some(code) => writeln!(f, "\tChosen: {}", describe(code))?,
+            None => writeln!(f, "\tChosen: none, kept as Oryx wrote it")?,
+        }
+        let keys = self
+            .keys
+            .iter()
+            .map(|(layer, key)| format!("layer {layer} key {key}"))
+            .collect::<Vec<_>>();
+        if keys.is_empty() {
+            writeln!(f, "\tBound to: no key")
+        } else {
+            writeln!(f, "\tBound to: {}", keys.join(", "))
+        }
+    }
+}
+
+impl fmt::Display for MacroAudit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.macros.is_empty() {
+            writeln!(f, "No macros.")?;
+        }
+        for audited in &self.macros {
+            write!(f, "{audited}")?;
+        }
+        if self.unreferenced.is_empty() {
+            writeln!(f, "Every dictionary entry is bound to a key.")
+        } else {
+            writeln!(f, "Never bound to a key:")?;
+            for &code in &self.unreferenced {
+                writeln!(f, "\t{}", describe(code))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Print how each macro of the selected export translates, where it's bound and which dictionary entries are unused.
+pub(crate) fn run(config: &Config, args: &Args, subcommand: Option<String>) -> Result<()> {
+    if subcommand.as_deref() != Some("audit") {
+        return Err(anyhow!("Usage: rover macros audit"));
+    }
+    let input = select::export(config, args)?;
+    println!("Auditing the macros of '{input}'...");
+    print!("{}", MacroAudit::new(config, &OryxExport::read(&input)?)?);
+    Ok(())
+}
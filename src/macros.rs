@@ -1,7 +1,51 @@
-use crate::{qmk_name, EXPORT_FOLDER};
-use anyhow::Result;
+use crate::{
+    config::Config,
+    logging::{info, progress},
+    qmk_name,
+    source_map::{Stage, TrackedFile},
+};
+use anyhow::{anyhow, Result};
 use enum_iterator::{all, Sequence};
-use std::{fs, io::Write, path::Path};
+use std::{collections::BTreeSet, io::Write};
+
+// Marks where a macro leaves the cursor, e.g. between the parentheses of "f($0)".
+const CURSOR: &str = "$0";
+
+// The case variants of a word which reads well capitalized and uppercase, e.g. "Return" and "RETURN".
+const WORD_VARIANTS: &[(Modifier, Case)] = &[
+    (Modifier::Shift, Case::Capitalized),
+    (Modifier::Alt, Case::Upper),
+];
+
+/// A language, whose macros the macro codes of the layers set to it resolve against.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Sequence, Clone, Copy, Default)]
+pub(crate) enum MacroSet {
+    #[default]
+    Cpp,
+    Rust,
+    Python,
+    Shell,
+}
+
+impl MacroSet {
+    /// e.g. "cpp", as named in rover.cfg.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            MacroSet::Cpp => "cpp",
+            MacroSet::Rust => "rust",
+            MacroSet::Python => "python",
+            MacroSet::Shell => "shell",
+        }
+    }
+
+    pub(crate) fn parse(name: &str) -> Result<Self> {
+        all::<MacroSet>()
+            .find(|set| set.name() == name)
+            .ok_or_else(|| {
+                anyhow!("Unknown macro set '{name}'. Expected cpp, rust, python or shell.")
+            })
+    }
+}
 
 #[derive(Debug, PartialEq, Sequence, Clone, Copy)]
 pub(crate) enum Macro {
@@ -36,6 +80,35 @@ pub(crate) enum Macro {
     While,
     ReinterpretCast,
     Function,
+    For,
+    In,
+    Fn,
+    Let,
+    Mut,
+    Impl,
+    Pub,
+    Match,
+    Trait,
+    Use,
+    Loop,
+    Println,
+    Def,
+    Lambda,
+    Elif,
+    Import,
+    Pass,
+    PythonNone,
+    PythonTrue,
+    PythonFalse,
+    SelfDot,
+    Print,
+    Then,
+    Fi,
+    Do,
+    Done,
+    Esac,
+    Echo,
+    Local,
 }
 
 impl From<Macro> for String {
@@ -59,7 +132,7 @@ impl From<Macro> for String {
             Macro::Class => "class",
             Macro::Struct => "struct",
             Macro::Namespace => "namespace",
-            Macro::Include => "#include",
+            Macro::Include => "#include \"$0\"",
             Macro::Define => "#define",
             Macro::IfDef => "#ifdef",
             Macro::Else => "#else",
@@ -70,66 +143,344 @@ impl From<Macro> for String {
             Macro::Typename => "typename",
             Macro::Auto => "auto",
             Macro::While => "while",
-            Macro::ReinterpretCast => "reinterpret_cast",
+            Macro::ReinterpretCast => "reinterpret_cast<$0>()",
             Macro::Function => "function",
+            Macro::For => "for",
+            Macro::In => "in",
+            Macro::Fn => "fn",
+            Macro::Let => "let",
+            Macro::Mut => "mut",
+            Macro::Impl => "impl",
+            Macro::Pub => "pub",
+            Macro::Match => "match",
+            Macro::Trait => "trait",
+            Macro::Use => "use",
+            Macro::Loop => "loop",
+            Macro::Println => "println!($0)",
+            Macro::Def => "def",
+            Macro::Lambda => "lambda",
+            Macro::Elif => "elif",
+            Macro::Import => "import",
+            Macro::Pass => "pass",
+            Macro::PythonNone => "None",
+            Macro::PythonTrue => "True",
+            Macro::PythonFalse => "False",
+            Macro::SelfDot => "self.",
+            Macro::Print => "print($0)",
+            Macro::Then => "then",
+            Macro::Fi => "fi",
+            Macro::Do => "do",
+            Macro::Done => "done",
+            Macro::Esac => "esac",
+            Macro::Echo => "echo",
+            Macro::Local => "local",
         }
         .to_string()
     }
 }
 
+/// A modifier which, held while a macro key is pressed, types one of the macro's case variants instead.
+#[derive(Clone, Copy, Debug)]
+enum Modifier {
+    Shift,
+    Alt,
+}
+
+impl Modifier {
+    /// QMK's mask of the modifier's left and right keys.
+    fn mask(self) -> &'static str {
+        match self {
+            Modifier::Shift => "MOD_MASK_SHIFT",
+            Modifier::Alt => "MOD_MASK_ALT",
+        }
+    }
+}
+
+/// A casing of a macro's text other than the dictionary's. There's no camelCase or snake_case: the dictionary's only
+/// multi-word entry, reinterpret_cast<$0>(), is a snippet, and snippets have no variants.
+#[derive(Clone, Copy, Debug)]
+enum Case {
+    /// e.g. "RETURN".
+    Upper,
+    /// e.g. "Return".
+    Capitalized,
+}
+
+impl Case {
+    fn apply(self, text: &str) -> String {
+        match self {
+            Case::Upper => text.to_uppercase(),
+            Case::Capitalized => capitalize(text),
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
 impl Macro {
-    fn into_send_string(self) -> Result<String> {
-        let value = String::from(self);
-        let mut send_string = "SEND_STRING(".to_string();
-        let mut first = true;
-        for c in value.chars() {
-            if first {
-                first = false;
-            } else {
-                send_string.push_str(" PETKAU_DELAY ");
+    /// The sets a macro belongs to, i.e. the languages in which it's what its abbreviations mean.
+    pub(crate) fn sets(self) -> &'static [MacroSet] {
+        use MacroSet::*;
+        match self {
+            Macro::Break
+            | Macro::NotEqual
+            | Macro::Return
+            | Macro::Continue
+            | Macro::While
+            | Macro::For => &[Cpp, Rust, Python, Shell],
+            Macro::In => &[Rust, Python, Shell],
+            Macro::DashArrow | Macro::Bool => &[Cpp, Rust, Python],
+            Macro::False | Macro::True => &[Cpp, Rust, Shell],
+            Macro::EqualsArrow | Macro::Static | Macro::Enum | Macro::Struct => &[Cpp, Rust],
+            Macro::Class => &[Cpp, Python],
+            Macro::Function => &[Cpp, Python, Shell],
+            Macro::Void
+            | Macro::NullPtr
+            | Macro::Virtual
+            | Macro::Override
+            | Macro::Namespace
+            | Macro::Include
+            | Macro::Define
+            | Macro::IfDef
+            | Macro::Else
+            | Macro::EndIf
+            | Macro::Public
+            | Macro::Private
+            | Macro::Template
+            | Macro::Typename
+            | Macro::Auto
+            | Macro::ReinterpretCast => &[Cpp],
+            Macro::Fn
+            | Macro::Let
+            | Macro::Mut
+            | Macro::Impl
+            | Macro::Pub
+            | Macro::Match
+            | Macro::Trait
+            | Macro::Use
+            | Macro::Loop
+            | Macro::Println => &[Rust],
+            Macro::Def
+            | Macro::Lambda
+            | Macro::Elif
+            | Macro::Import
+            | Macro::Pass
+            | Macro::PythonNone
+            | Macro::PythonTrue
+            | Macro::PythonFalse
+            | Macro::SelfDot
+            | Macro::Print => &[Python],
+            Macro::Then
+            | Macro::Fi
+            | Macro::Do
+            | Macro::Done
+            | Macro::Esac
+            | Macro::Echo
+            | Macro::Local => &[Shell],
+        }
+    }
+
+    /// Case variants, each typed instead of the text while its modifier is held. Entries opt in by being listed
+    /// here, and only plain words do: not operators, preprocessor directives, snippets with a cursor placeholder
+    /// (e.g. reinterpret_cast<$0>()) or words whose case their language fixes (e.g. Python's True, False and None).
+    fn variants(self) -> &'static [(Modifier, Case)] {
+        match self {
+            Macro::Void
+            | Macro::Break
+            | Macro::Return
+            | Macro::Bool
+            | Macro::False
+            | Macro::True
+            | Macro::Continue
+            | Macro::Virtual
+            | Macro::Override
+            | Macro::Static
+            | Macro::Enum
+            | Macro::Class
+            | Macro::Struct
+            | Macro::Namespace
+            | Macro::Public
+            | Macro::Private
+            | Macro::Template
+            | Macro::Typename
+            | Macro::Auto
+            | Macro::While
+            | Macro::Function => WORD_VARIANTS,
+            _ => &[],
+        }
+    }
+
+    /// e.g. Macro::Return ("return").
+    fn describe(self) -> String {
+        format!("Macro::{self:?} (\"{}\")", String::from(self))
+    }
+
+    /// Milliseconds between the keys it types.
+    fn delay(self, config: &Config) -> u64 {
+        config
+            .macro_delays
+            .get(&format!("{self:?}"))
+            .copied()
+            .unwrap_or(config.macro_delay)
+    }
+
+    /// The statement typing the text (its own or a case variant), as configured by MACRO_DELAY, MACRO_ENCODING
+    /// and MACRO_SENDER.
+    fn into_send_string(self, value: &str, config: &Config) -> Result<String> {
+        let (value, lefts) = split_cursor(value);
+        let value = value.as_str();
+        let delay = self.delay(config);
+        let compact = match config.macro_encoding.as_str() {
+            "taps" => false,
+            // send_string() types any printable ASCII character.
+            "compact" => value.chars().all(|c| c == ' ' || c.is_ascii_graphic()),
+            name => {
+                return Err(anyhow!(
+                    "Unknown macro encoding '{name}'. Expected taps or compact."
+                ))
+            }
+        };
+        match config.macro_sender.as_str() {
+            // A string literal can't hold delays, so a delayed macro is typed a tap at a time.
+            "SEND_STRING" if compact && delay == 0 => Ok(format!(
+                "SEND_STRING({});",
+                with_lefts(literal(value), lefts, " ")
+            )),
+            "SEND_STRING" => {
+                let separator = if delay == config.macro_delay {
+                    " PETKAU_DELAY ".to_string()
+                } else {
+                    format!(" SS_DELAY({delay}) ")
+                };
+                let taps = with_lefts(taps(value, &separator)?, lefts, &separator);
+                Ok(format!("SEND_STRING({taps});"))
             }
-            send_string.push_str(&qmk_name::from_char(c)?);
+            "send_string_with_delay" => {
+                if delay > u8::MAX.into() {
+                    return Err(anyhow!(
+                        "send_string_with_delay can't wait {delay} ms between keys of {}; the most is {}.",
+                        self.describe(),
+                        u8::MAX
+                    ));
+                }
+                let text = if compact {
+                    literal(value)
+                } else {
+                    taps(value, " ")?
+                };
+                let text = with_lefts(text, lefts, " ");
+                Ok(format!("send_string_with_delay({text}, {delay});"))
+            }
+            name => Err(anyhow!(
+                "Unknown macro sender '{name}'. Expected SEND_STRING or send_string_with_delay."
+            )),
         }
-        send_string.push_str(");");
-        Ok(send_string)
     }
 }
 
-pub(crate) fn export_petkau_macros_inl() -> Result<()> {
-    print!("Exporting petkau_macros.inl...");
-    let petkau_macros_inl =
-        &mut fs::File::create(Path::new(EXPORT_FOLDER).join("petkau_macros.inl"))?;
+/// The text without its cursor placeholder, and how many characters follow the placeholder.
+fn split_cursor(value: &str) -> (String, usize) {
+    match value.split_once(CURSOR) {
+        Some((before, after)) => (format!("{before}{after}"), after.chars().count()),
+        None => (value.to_string(), 0),
+    }
+}
+
+/// The keys typing the text, followed by a left arrow tap per character to move the cursor back over.
+fn with_lefts(keys: String, lefts: usize, separator: &str) -> String {
+    keys + &format!("{separator}SS_TAP(X_LEFT)").repeat(lefts)
+}
+
+/// An SS_TAP(...) for each character, joined by the separator.
+fn taps(value: &str, separator: &str) -> Result<String> {
+    Ok(value
+        .chars()
+        .map(qmk_name::from_char)
+        .collect::<Result<Vec<_>>>()?
+        .join(separator))
+}
+
+/// A C string literal of the text.
+fn literal(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Write petkau_macros.inl with the macros of the given sets, i.e. those some layer's macro codes resolve against.
+pub(crate) fn export_petkau_macros_inl(config: &Config, sets: &BTreeSet<MacroSet>) -> Result<()> {
+    let macros = all::<Macro>()
+        .filter(|value| value.sets().iter().any(|set| sets.contains(set)))
+        .collect::<Vec<_>>();
+    progress!("Exporting petkau_macros.inl...");
+    let petkau_macros_inl = &mut TrackedFile::create(&config.export_folder(), "petkau_macros.inl")?;
+    petkau_macros_inl.set_origin(Stage::MacroTranslation, "petkau_keycodes");
     writeln!(petkau_macros_inl, "enum petkau_keycodes")?;
     writeln!(petkau_macros_inl, "{{")?;
     writeln!(petkau_macros_inl, "\tRGB_SLD = ML_SAFE_RANGE,")?;
-    for value in all::<Macro>() {
+    for &value in &macros {
+        petkau_macros_inl.set_origin(Stage::MacroTranslation, value.describe());
         writeln!(petkau_macros_inl, "\tPETKAU_MACRO_{:?},", value)?;
     }
+    petkau_macros_inl.set_origin(Stage::MacroTranslation, "process_record_macros");
     writeln!(petkau_macros_inl, "}};")?;
     writeln!(petkau_macros_inl)?;
-    writeln!(petkau_macros_inl, "#define PETKAU_DELAY SS_DELAY(0)")?;
+    writeln!(
+        petkau_macros_inl,
+        "#define PETKAU_DELAY SS_DELAY({})",
+        config.macro_delay
+    )?;
     writeln!(petkau_macros_inl)?;
     writeln!(
         petkau_macros_inl,
         "bool process_record_macros(uint16_t keycode, keyrecord_t *record)"
     )?;
     writeln!(petkau_macros_inl, "{{")?;
+    // A one-shot modifier counts as held, but is used up by the macro rather than restored after it.
+    writeln!(petkau_macros_inl, "\tconst uint8_t held_mods = get_mods();")?;
+    writeln!(
+        petkau_macros_inl,
+        "\tconst uint8_t mods = held_mods | get_oneshot_mods();"
+    )?;
     writeln!(petkau_macros_inl, "\tswitch (keycode)")?;
     writeln!(petkau_macros_inl, "\t{{")?;
-    for value in all::<Macro>() {
-        writeln!(
-            petkau_macros_inl,
-            "\tcase PETKAU_MACRO_{:?}: {} break;",
-            value,
-            value.into_send_string()?
-        )?;
-    }writeln!(
+    for &value in &macros {
+        petkau_macros_inl.set_origin(Stage::MacroTranslation, value.describe());
+        let text = String::from(value);
+        let send_string = value.into_send_string(&text, config)?;
+        if value.variants().is_empty() {
+            writeln!(
+                petkau_macros_inl,
+                "\tcase PETKAU_MACRO_{:?}: {send_string} break;",
+                value
+            )?;
+            continue;
+        }
+        // Release the modifier while typing the variant, so it doesn't change the keys typed.
+        writeln!(petkau_macros_inl, "\tcase PETKAU_MACRO_{:?}:", value)?;
+        for (i, (modifier, case)) in value.variants().iter().enumerate() {
+            writeln!(
+                petkau_macros_inl,
+                "\t\t{}if (mods & {}) {{ clear_mods(); clear_oneshot_mods(); {} set_mods(held_mods); }}",
+                if i == 0 { "" } else { "else " },
+                modifier.mask(),
+                value.into_send_string(&case.apply(&text), config)?
+            )?;
+        }
+        writeln!(petkau_macros_inl, "\t\telse {{ {send_string} }}")?;
+        writeln!(petkau_macros_inl, "\t\tbreak;")?;
+    }
+    petkau_macros_inl.set_origin(Stage::MacroTranslation, "process_record_macros");
+    writeln!(
         petkau_macros_inl,
         "\tdefault: return false;"
     )?;
     writeln!(petkau_macros_inl, "\t}}")?;
     writeln!(petkau_macros_inl, "\treturn true;")?;
     writeln!(petkau_macros_inl, "}};")?;
-    println!("done.");
+    info!("done.");
     Ok(())
 }
@@ -1,93 +1,82 @@
-use crate::temp_folder::TempFolder;
-use anyhow::Result;
-use std::{
-    fs,
-    io::{self, Write},
-    path::Path,
-    process::Command,
-};
+use anyhow::{anyhow, Result};
 
-// Export folder is hard-coded to "C:\src\qmk_firmware\keyboards\moonlander\keymaps\chrispetkau".
-const EXPORT_FOLDER: &str = "C:/src/qmk_firmware/keyboards/moonlander/keymaps/chrispetkau";
+// Files in the export folder which rover generates, and so stages and commits.
+const GENERATED_FILES: [&str; 5] = [
+    "config.h",
+    "rules.mk",
+    "keymap.c",
+    "petkau_tap_dance.inl",
+    "petkau_macros.inl",
+];
 
+mod args;
+mod checksum;
 mod command;
+mod commit_message;
+mod compile;
+mod config;
+mod diagnostics;
+mod diff;
+mod firmware;
+mod flash;
+mod git;
+mod history;
 mod keymap;
+mod layout;
+mod logging;
+mod macro_audit;
 mod macros;
+mod oryx;
+mod ownership;
+mod pipeline;
+mod profile;
 mod qmk_name;
-mod temp_folder;
+mod report;
+mod select;
+mod size_budget;
+mod source;
+mod source_map;
+mod staging;
+mod time_stamp;
+mod watch;
 mod zip;
 mod custom_keycode;
 
 fn main() -> Result<()> {
-    // Find the most recent downloaded file with prefix "moonlander_" and extension ".zip".
-    print!("Locating most recent moonlander_* source code .zip file...");
-    let zip = zip::find_most_recent_download()?;
-    println!("found '{zip}'.");
-
-    let temp_folder = TempFolder::new()?;
-
-    println!("Unzipping '{zip}' to '{}' folder...", temp_folder::NAME);
-    zip::extract_files_to_temp(&zip)?;
-    println!("...done.");
-
-    // Update "config.h" via "temp\config.h": copy every line, then #include "petkau_config.inl".
-    print!("Updating config.h...");
-    let config = &mut fs::File::create(Path::new(EXPORT_FOLDER).join("config.h"))?;
-    io::copy(
-        &mut fs::File::open(Path::new(temp_folder::NAME).join("config.h"))?,
-        config,
-    )?;
-    writeln!(config, "#include \"petkau_config.inl\"")?;
-    println!("done.");
-
-    // Update "rules.mk" by just overwriting it. There are no customizations to this file.
-    print!("Updating rules.mk...");
-    let rules = &mut fs::File::create(Path::new(EXPORT_FOLDER).join("rules.mk"))?;
-    io::copy(
-        &mut fs::File::open(Path::new(temp_folder::NAME).join("rules.mk"))?,
-        rules,
-    )?;
-    writeln!(rules, "DYNAMIC_TAPPING_TERM_ENABLE = yes")?;
-    println!("done.");
-
-    keymap::update_keymap_c()?;
-
-    temp_folder.delete()?;
-
-    macros::export_petkau_macros_inl()?;
-
-    // > C:/QMK_MSYS/conemu/ConEmu64.exe -NoSingle -NoUpdate -icon "C:/QMK_MSYS/icon.ico" -title "QMK MSYS" -run "C:/QMK_MSYS/usr/bin/bash.exe" -l -t -c "qmk compile -kb moonlander -km chrispetkau"
-    command::run(
-        "Compiling QMK firmware",
-        Command::new("C:/QMK_MSYS/conemu/ConEmu64.exe").args([
-            "-NoSingle",
-            "-NoUpdate",
-            "-icon",
-            "C:/QMK_MSYS/icon.ico",
-            "-title",
-            "QMK MSYS",
-            "-run",
-            "C:/QMK_MSYS/usr/bin/bash.exe",
-            "-l",
-            "-t",
-            "-c",
-            "qmk compile -j 0 -kb moonlander -km chrispetkau",
-        ]),
-    )?;
+    let args = args::Args::parse()?;
+    logging::init(args.verbosity, args.log_json.as_deref())?;
+    let result = run(&args);
+    if let Err(error) = &result {
+        logging::record(logging::Level::Warn, &format!("Error: {error:#}"));
+    }
+    // A report that can't be written shouldn't hide how the run went.
+    if let Some(path) = &args.report {
+        if let Err(error) = report::write(path, result.as_ref().err()) {
+            logging::warning!("Warning: {error:#}");
+        }
+    }
+    result
+}
 
-    command::run(
-        "Flashing keyboard",
-        Command::new("C:/Program Files (x86)/Wally/Wally.exe")
-            .args(["C:/src/qmk_firmware/moonlander_chrispetkau.bin"]),
-    )?;
+fn run(args: &args::Args) -> Result<()> {
+    let config = config::get()?;
+    let mut positional = args.positional.iter().cloned();
+    match positional.next().as_deref() {
+        Some("diff") => return diff::run(positional.next(), positional.next()),
+        Some("rollback") => return history::rollback(config, positional.next()),
+        Some("watch") => return watch::run(config, args),
+        Some("macros") => return macro_audit::run(config, args, positional.next()),
+        Some(command) => return Err(anyhow!("Unknown command '{command}'.")),
+        None => {}
+    }
 
-    // Stage and commit all changes via git.
-    command::run(
-        "Committing changes",
-        Command::new("git")
-            .current_dir(EXPORT_FOLDER)
-            .args(["commit", "-am", &zip]),
-    )?;
+    let export = logging::stage("input", || {
+        source::OryxExport::read(&select::export(config, args)?)
+    })?;
+    report::input(&export.name)?;
+    pipeline::generate(config, &export, args.force)?;
+    let firmware = pipeline::build(config)?;
+    pipeline::deliver(config, &export, &firmware, args.tag)?;
 
     Ok(())
 }
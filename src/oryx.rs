@@ -0,0 +1,305 @@
+use crate::{
+    config::Config,
+    logging::{info, progress},
+    zip,
+};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::fs;
+
+// Asks for the revision's id, the QMK version it targets and the layout's title.
+const LAYOUT_QUERY: &str = "query getLayout($hashId: String!, $revisionId: String!, $geometry: String) {layout(hashId: $hashId, geometry: $geometry, revisionId: $revisionId) { revision { hashId, qmkVersion, title }}}";
+
+/// Sends the requests the Oryx API needs, so a local stand-in server (or none at all) can replace Oryx.
+pub(crate) trait HttpClient {
+    /// POST a JSON body and parse the JSON response.
+    fn post_json(&self, url: &str, body: &Value) -> Result<Value>;
+    /// GET the response body.
+    fn get(&self, url: &str) -> Result<Vec<u8>>;
+}
+
+/// Talks to whatever server the URL names.
+pub(crate) struct Ureq;
+
+impl HttpClient for Ureq {
+    fn post_json(&self, url: &str, body: &Value) -> Result<Value> {
+        Ok(ureq::post(url)
+            .send_json(body)?
+            .body_mut()
+            .read_json::<Value>()?)
+    }
+
+    fn get(&self, url: &str) -> Result<Vec<u8>> {
+        Ok(ureq::get(url).call()?.body_mut().read_to_vec()?)
+    }
+}
+
+/// A layout revision as Oryx describes it.
+struct Revision {
+    hash_id: String,
+    qmk_version: String,
+    title: String,
+}
+
+/// Split "<layout id>[/<revision id>]" into the layout and revision ids, the revision defaulting to "latest".
+pub(crate) fn layout_and_revision(revision: &str) -> (&str, &str) {
+    revision.split_once('/').unwrap_or((revision, "latest"))
+}
+
+/// Download the source .zip file of a layout revision ("latest" for the newest) from Oryx into the import folder,
+/// returning its file name.
+pub(crate) fn fetch(
+    client: &dyn HttpClient,
+    config: &Config,
+    layout_id: &str,
+    revision_id: &str,
+) -> Result<String> {
+    progress!("Querying Oryx for layout '{layout_id}' revision '{revision_id}'...");
+    let revision = query_revision(client, config, layout_id, revision_id)?;
+    info!(
+        "found '{}' revision '{}' (QMK {}).",
+        revision.title, revision.hash_id, revision.qmk_version
+    );
+
+    let file_name = format!(
+        "moonlander_{}_{}.zip",
+        slug(&revision.title),
+        revision.hash_id
+    );
+    progress!("Downloading '{file_name}'...");
+    let contents = client.get(&format!("{}/source/{}", config.oryx_url, revision.hash_id))?;
+    // Write under another name first so a half-written or invalid download never looks like a moonlander_* .zip
+    // file.
+    let partial = config.import_folder.join(format!("{file_name}.part"));
+    fs::write(&partial, contents)?;
+    if !zip::is_valid(&partial) {
+        info!("invalid.");
+        fs::remove_file(&partial)?;
+        return Err(anyhow!(
+            "Oryx's source for '{file_name}' isn't a .zip file."
+        ));
+    }
+    fs::rename(&partial, config.import_folder.join(&file_name))?;
+    info!("done.");
+    Ok(file_name)
+}
+
+fn query_revision(
+    client: &dyn HttpClient,
+    config: &Config,
+    layout_id: &str,
+    revision_id: &str,
+) -> Result<Revision> {
+    let response = client.post_json(
+        &format!("{}/graphql", config.oryx_url),
+        &json!({
+            "operationName": "getLayout",
+            "query": LAYOUT_QUERY,
+            "variables": {
+                "hashId": layout_id,
+                "revisionId": revision_id,
+                "geometry": config.profile().oryx_geometry,
+            },
+        }),
+    )?;
+    if let Some(errors) = response.get("errors").and_then(Value::as_array) {
+        let messages = errors
+            .iter()
+            .filter_map(|error| error.get("message").and_then(Value::as_str))
+            .collect::<Vec<_>>();
+        return Err(anyhow!("Oryx says: {}", messages.join("; ")));
+    }
+    let revision = response
+        .pointer("/data/layout/revision")
+        .filter(|revision| !revision.is_null())
+        .ok_or_else(|| anyhow!("Oryx has no layout '{layout_id}' revision '{revision_id}'."))?;
+    let field = |name: &str| {
+        revision
+            .get(name)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Oryx's revision has no {name}."))
+    };
+    Ok(Revision {
+        hash_id: field("hashId")?,
+        qmk_version: field("qmkVersion").unwrap_or_default(),
+        title: field("title").unwrap_or_default(),
+    })
+}
+
+/// The title in lowercase with runs of anything but letters and digits as single dashes, e.g. "My Layout!" becomes
+/// "my-layout".
+fn slug(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        cell::RefCell,
+        io::{Cursor, Write},
+    };
+
+    /// Answers every query with `response` and every download with `source`, remembering what was asked.
+    struct FakeOryx {
+        response: Value,
+        source: Vec<u8>,
+        queries: RefCell<Vec<(String, Value)>>,
+        downloads: RefCell<Vec<String>>,
+    }
+
+    impl FakeOryx {
+        fn new(response: Value, source: Vec<u8>) -> Self {
+            Self {
+                response,
+                source,
+                queries: RefCell::default(),
+                downloads: RefCell::default(),
+            }
+        }
+    }
+
+    impl HttpClient for FakeOryx {
+        fn post_json(&self, url: &str, body: &Value) -> Result<Value> {
+            self.queries
+                .borrow_mut()
+                .push((url.to_string(), body.clone()));
+            Ok(self.response.clone())
+        }
+
+        fn get(&self, url: &str) -> Result<Vec<u8>> {
+            self.downloads.borrow_mut().push(url.to_string());
+            Ok(self.source.clone())
+        }
+    }
+
+    fn revision(hash_id: &str) -> Value {
+        json!({ "data": { "layout": { "revision": {
+            "hashId": hash_id,
+            "qmkVersion": "24.0",
+            "title": "My Layout!",
+        }}}})
+    }
+
+    fn source_zip() -> Vec<u8> {
+        let mut writer = ::zip::ZipWriter::new(Cursor::new(Vec::new()));
+        writer
+            .start_file(
+                "moonlander_my-layout_source/keymap.c",
+                ::zip::write::FileOptions::default(),
+            )
+            .unwrap();
+        writer.write_all(b"#include QMK_KEYBOARD_H\n").unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// A config downloading into an empty folder of its own.
+    fn config(test: &str) -> Config {
+        let import_folder = std::env::temp_dir().join(format!("rover-oryx-{test}"));
+        let _ = fs::remove_dir_all(&import_folder);
+        fs::create_dir_all(&import_folder).unwrap();
+        Config {
+            import_folder,
+            oryx_url: "http://oryx.test".to_string(),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn revision_defaults_to_latest() {
+        assert_eq!(layout_and_revision("aBcDe"), ("aBcDe", "latest"));
+        assert_eq!(layout_and_revision("aBcDe/qrs34"), ("aBcDe", "qrs34"));
+    }
+
+    #[test]
+    fn fetches_revision_into_import_folder() {
+        let config = config("fetch");
+        let client = FakeOryx::new(revision("qrs34"), source_zip());
+        let file_name = fetch(&client, &config, "aBcDe", "latest").unwrap();
+        assert_eq!(file_name, "moonlander_my-layout_qrs34.zip");
+        assert!(config.import_folder.join(&file_name).is_file());
+        assert!(!config
+            .import_folder
+            .join(format!("{file_name}.part"))
+            .exists());
+
+        let queries = client.queries.borrow();
+        let (url, body) = &queries[0];
+        assert_eq!(url, "http://oryx.test/graphql");
+        assert_eq!(body["variables"]["hashId"], "aBcDe");
+        assert_eq!(body["variables"]["revisionId"], "latest");
+        assert_eq!(body["variables"]["geometry"], "moonlander");
+        // The download is of the revision Oryx resolved "latest" to.
+        assert_eq!(
+            *client.downloads.borrow(),
+            ["http://oryx.test/source/qrs34"]
+        );
+        let _ = fs::remove_dir_all(&config.import_folder);
+    }
+
+    #[test]
+    fn asks_for_given_revision() {
+        let config = config("given");
+        let client = FakeOryx::new(revision("qrs34"), source_zip());
+        fetch(&client, &config, "aBcDe", "qrs34").unwrap();
+        assert_eq!(
+            client.queries.borrow()[0].1["variables"]["revisionId"],
+            "qrs34"
+        );
+        let _ = fs::remove_dir_all(&config.import_folder);
+    }
+
+    #[test]
+    fn reports_oryx_errors() {
+        let config = config("errors");
+        let client = FakeOryx::new(
+            json!({ "errors": [{ "message": "Layout not found" }] }),
+            source_zip(),
+        );
+        let error = fetch(&client, &config, "aBcDe", "latest").unwrap_err();
+        assert_eq!(error.to_string(), "Oryx says: Layout not found");
+        assert!(client.downloads.borrow().is_empty());
+        let _ = fs::remove_dir_all(&config.import_folder);
+    }
+
+    #[test]
+    fn reports_missing_revision() {
+        let config = config("missing");
+        let client = FakeOryx::new(
+            json!({ "data": { "layout": { "revision": null } } }),
+            source_zip(),
+        );
+        let error = fetch(&client, &config, "aBcDe", "qrs34").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Oryx has no layout 'aBcDe' revision 'qrs34'."
+        );
+        let _ = fs::remove_dir_all(&config.import_folder);
+    }
+
+    #[test]
+    fn rejects_source_which_isnt_zip() {
+        let config = config("invalid");
+        let client = FakeOryx::new(revision("qrs34"), b"<html>Not found</html>".to_vec());
+        let error = fetch(&client, &config, "aBcDe", "latest").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Oryx's source for 'moonlander_my-layout_qrs34.zip' isn't a .zip file."
+        );
+        assert!(!config
+            .import_folder
+            .join("moonlander_my-layout_qrs34.zip")
+            .exists());
+        assert!(!config
+            .import_folder
+            .join("moonlander_my-layout_qrs34.zip.part")
+            .exists());
+        let _ = fs::remove_dir_all(&config.import_folder);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{checksum, config::Config, logging::warning};
+use anyhow::{anyhow, Result};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// Refuse to replace a file whose contents changed since rover last generated it, unless the user agrees or
+/// `force` is set. Files rover has no record of are replaced as before.
+pub(crate) fn check(config: &Config, paths: &[PathBuf], force: bool) -> Result<()> {
+    let manifest = read_manifest(&config.manifest_file())?;
+    let mut edited = Vec::new();
+    for path in paths {
+        let Some(generated) = manifest.get(&key(path)) else {
+            continue;
+        };
+        if path.exists() && checksum::sha256_file(path)? != *generated {
+            edited.push(path.display().to_string());
+        }
+    }
+    if edited.is_empty() {
+        return Ok(());
+    }
+
+    warning!("Edited by hand since rover generated them:");
+    for path in &edited {
+        warning!("\t{path}");
+    }
+    if force {
+        warning!("Replacing them anyway (--force).");
+        return Ok(());
+    }
+    print!("Replace them, losing the edits? [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if answer.trim().eq_ignore_ascii_case("y") {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Not replacing files edited by hand. Move the edits into petkau_*.inl files, or run with --force."
+        ))
+    }
+}
+
+/// Record the contents of the files rover just generated.
+pub(crate) fn record(config: &Config, paths: &[PathBuf]) -> Result<()> {
+    let manifest_file = config.manifest_file();
+    let mut manifest = read_manifest(&manifest_file)?;
+    for path in paths {
+        manifest.insert(key(path), checksum::sha256_file(path)?);
+    }
+    if let Some(folder) = manifest_file.parent() {
+        fs::create_dir_all(folder)?;
+    }
+    fs::write(
+        &manifest_file,
+        manifest
+            .iter()
+            .map(|(path, sha256)| format!("{path} = {sha256}\n"))
+            .collect::<String>(),
+    )?;
+    Ok(())
+}
+
+fn read_manifest(manifest_file: &Path) -> Result<BTreeMap<String, String>> {
+    if !manifest_file.exists() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(fs::read_to_string(manifest_file)?
+        .lines()
+        .filter_map(|line| line.rsplit_once(" = "))
+        .map(|(path, sha256)| (path.to_string(), sha256.to_string()))
+        .collect())
+}
+
+fn key(path: &Path) -> String {
+    path.display().to_string()
+}
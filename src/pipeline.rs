@@ -0,0 +1,114 @@
+use crate::{
+    commit_message, compile,
+    config::Config,
+    firmware::{self, Firmware},
+    flash, git, history, keymap,
+    logging::{self, info, progress},
+    macros, ownership, report, size_budget,
+    source::OryxExport,
+    source_map::{Stage, TrackedFile},
+    staging, GENERATED_FILES,
+};
+use anyhow::Result;
+use std::{io::Write, time::SystemTime};
+
+/// Generate the keymap's files in the export folder from the Oryx export. Either every file is replaced or, on
+/// failure, none is. Files edited by hand since rover generated them are only replaced with `force` or the user's
+/// agreement.
+pub(crate) fn generate(config: &Config, export: &OryxExport, force: bool) -> Result<()> {
+    logging::stage("generate", || generate_atomically(config, export, force))
+}
+
+fn generate_atomically(config: &Config, export: &OryxExport, force: bool) -> Result<()> {
+    match write_generated_files(config, export)
+        .and_then(|()| ownership::check(config, &staging::paths(), force))
+    {
+        Ok(()) => {
+            let paths = staging::paths();
+            staging::commit()?;
+            report::files(&paths)?;
+            ownership::record(config, &paths)
+        }
+        Err(error) => {
+            staging::abort();
+            Err(error)
+        }
+    }
+}
+
+fn write_generated_files(config: &Config, export: &OryxExport) -> Result<()> {
+    if !export.extra_files.is_empty() {
+        info!(
+            "'{}' has files rover doesn't use: {}.",
+            export.name,
+            export.extra_files.join(", ")
+        );
+    }
+    for file_name in &export.missing_files {
+        info!(
+            "No {file_name} beside '{}'. Using an empty one.",
+            export.name
+        );
+    }
+
+    // Update "config.h": copy every line, then #include "petkau_config.inl".
+    progress!("Updating config.h...");
+    let config_h = &mut TrackedFile::create(&config.export_folder(), "config.h")?;
+    config_h.set_origin(Stage::OryxPassthrough, "config.h");
+    config_h.write_all(export.config_h.as_bytes())?;
+    config_h.set_origin(Stage::Override, "petkau_config.inl include");
+    writeln!(config_h, "#include \"petkau_config.inl\"")?;
+    info!("done.");
+
+    // Update "rules.mk" by just overwriting it. There are no customizations to this file.
+    progress!("Updating rules.mk...");
+    let rules = &mut TrackedFile::create(&config.export_folder(), "rules.mk")?;
+    rules.set_origin(Stage::OryxPassthrough, "rules.mk");
+    rules.write_all(export.rules_mk.as_bytes())?;
+    rules.set_origin(Stage::Override, "DYNAMIC_TAPPING_TERM_ENABLE");
+    writeln!(rules, "DYNAMIC_TAPPING_TERM_ENABLE = yes")?;
+    info!("done.");
+
+    let macro_sets = logging::stage("keymap", || {
+        keymap::update_keymap_c(config, &export.keymap_c)
+    })?;
+    logging::stage("macros", || {
+        macros::export_petkau_macros_inl(config, &macro_sets)
+    })?;
+    Ok(())
+}
+
+/// Compile the generated files, returning the verified firmware image.
+pub(crate) fn build(config: &Config) -> Result<Firmware> {
+    let build_start = SystemTime::now();
+    let build_output = logging::stage("compile", || compile::compile(config))?;
+    let firmware = logging::stage("firmware", || firmware::locate(config, build_start))?;
+    logging::stage("size check", || {
+        size_budget::check(config, &firmware, &build_output)
+    })?;
+    report::firmware(&firmware);
+    Ok(firmware)
+}
+
+/// Flash the firmware, commit the generated files and archive the build.
+pub(crate) fn deliver(
+    config: &Config,
+    export: &OryxExport,
+    firmware: &Firmware,
+    tag: bool,
+) -> Result<()> {
+    logging::stage("flash", || flash::flash(config, &firmware.path))?;
+
+    // Stage and commit the generated files, describing what changed since the previous export.
+    let commit = logging::stage("commit", || {
+        let message = commit_message::compose(config, export)?;
+        git::commit(&config.export_folder(), &GENERATED_FILES, &message, tag)
+    })?;
+
+    // When nothing changed, the build is of the commit already at HEAD.
+    let commit = commit.or_else(|| git::head_id(&config.export_folder()));
+    report::commit(commit);
+    logging::stage("archive", || {
+        history::archive(config, &export.name, firmware, commit)
+    })
+}
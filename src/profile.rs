@@ -0,0 +1,47 @@
+/// What rover knows about flashing each keyboard it supports.
+pub(crate) struct KeyboardProfile {
+    /// Default flasher: "wally", "dfu-util", "qmk" or "manual".
+    pub(crate) flasher: &'static str,
+    /// USB vendor:product id the board enumerates as while in its bootloader.
+    pub(crate) bootloader_usb_id: &'static str,
+    /// dfu-util --dfuse-address, for boards with a DFU bootloader.
+    pub(crate) dfu_address: Option<&'static str>,
+    /// Bytes of flash available to the firmware, excluding the bootloader, if known.
+    pub(crate) flash_capacity: Option<u64>,
+    /// Oryx's name for the keyboard's geometry, for fetching layouts.
+    pub(crate) oryx_geometry: Option<&'static str>,
+}
+
+/// The profile of the given QMK keyboard, falling back to printing instructions for unknown boards.
+pub(crate) fn for_keyboard(keyboard: &str) -> KeyboardProfile {
+    match keyboard {
+        "moonlander" => KeyboardProfile {
+            flasher: "wally",
+            bootloader_usb_id: "0483:df11",
+            dfu_address: Some("0x08000000:leave"),
+            flash_capacity: Some(256 * 1024),
+            oryx_geometry: Some("moonlander"),
+        },
+        "planck/ez" | "planck/ez/glow" => KeyboardProfile {
+            flasher: "wally",
+            bootloader_usb_id: "0483:df11",
+            dfu_address: Some("0x08000000:leave"),
+            flash_capacity: Some(256 * 1024),
+            oryx_geometry: Some("planck-ez"),
+        },
+        "ergodox_ez" | "ergodox_ez/glow" | "ergodox_ez/shine" => KeyboardProfile {
+            flasher: "wally",
+            bootloader_usb_id: "16c0:0478",
+            dfu_address: None,
+            flash_capacity: Some(32256),
+            oryx_geometry: Some("ergodox-ez"),
+        },
+        _ => KeyboardProfile {
+            flasher: "manual",
+            bootloader_usb_id: "",
+            dfu_address: None,
+            flash_capacity: None,
+            oryx_geometry: None,
+        },
+    }
+}
@@ -42,6 +42,8 @@ pub(crate) fn to_char(s: &str, shifted: bool) -> Result<char> {
         "MINUS" => Ok(if shifted { '_' } else { '-' }),
         "DOT" => Ok(if shifted { '>' } else { '.' }),
         "COMMA" => Ok(if shifted { '<' } else { ',' }),
+        "QUOTE" => Ok(if shifted { '"' } else { '\'' }),
+        "SPACE" => Ok(' '),
         _ => Err(anyhow!("No known QMK name for {s}.")),
     }
 }
@@ -49,7 +51,7 @@ pub(crate) fn to_char(s: &str, shifted: bool) -> Result<char> {
 pub(crate) fn from_char(c: char) -> Result<String> {
     match c {
         'a'..='z' | '0'..='9' => Ok(format!("SS_TAP(X_{})", c.to_ascii_uppercase())),
-        'A'..='Z' => Ok(format!("SS_LSFT(SS_TAP({c}))")),
+        'A'..='Z' => Ok(format!("SS_LSFT(SS_TAP(X_{c}))")),
         '=' => Ok("SS_TAP(X_EQUAL)".to_string()),
         '+' => Ok("SS_LSFT(SS_TAP(X_EQUAL))".to_string()),
         '-' => Ok("SS_TAP(X_MINUS)".to_string()),
@@ -67,6 +69,9 @@ pub(crate) fn from_char(c: char) -> Result<String> {
         ')' => Ok("SS_LSFT(SS_TAP(X_0))".to_string()),
         '<' => Ok("SS_LSFT(SS_TAP(X_COMMA))".to_string()),
         '>' => Ok("SS_LSFT(SS_TAP(X_DOT))".to_string()),
+        '"' => Ok("SS_LSFT(SS_TAP(X_QUOTE))".to_string()),
+        '\'' => Ok("SS_TAP(X_QUOTE)".to_string()),
+        ' ' => Ok("SS_TAP(X_SPACE)".to_string()),
         _ => Err(anyhow!("No known QMK name for {c}.")),
     }
 }
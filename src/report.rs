@@ -0,0 +1,121 @@
+use crate::{checksum, firmware::Firmware, source};
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+/// What a run did, gathered as it goes and written by --report as one JSON object.
+#[derive(Default)]
+struct Report {
+    input: Option<Value>,
+    stages: Vec<Value>,
+    macro_translations: Vec<Value>,
+    files: Vec<Value>,
+    firmware: Option<Value>,
+    commit: Option<String>,
+}
+
+fn report() -> &'static Mutex<Report> {
+    static REPORT: OnceLock<Mutex<Report>> = OnceLock::new();
+    REPORT.get_or_init(Default::default)
+}
+
+fn update(change: impl FnOnce(&mut Report)) {
+    if let Ok(mut report) = report().lock() {
+        change(&mut report);
+    }
+}
+
+/// Record the input and the checksum of each of its files.
+pub(crate) fn input(name: &str) -> Result<()> {
+    let files = hashes(&source::files(name)?)?;
+    update(|report| report.input = Some(json!({ "name": name, "files": files })));
+    Ok(())
+}
+
+/// Record a finished stage of the pipeline.
+pub(crate) fn stage(name: &str, status: &str, duration: Duration) {
+    update(|report| {
+        report.stages.push(json!({
+            "name": name,
+            "status": status,
+            "duration_ms": duration.as_millis() as u64,
+        }))
+    });
+}
+
+/// Record how ST_MACRO_`index` was translated against a macro set: the text it types, if it could be decoded, every
+/// keycode of the set that text abbreviates, and the one chosen (none to keep the macro as Oryx wrote it).
+pub(crate) fn macro_translation(
+    index: usize,
+    set: &str,
+    code: Option<&str>,
+    candidates: Vec<String>,
+    chosen: Option<String>,
+) {
+    update(|report| {
+        report.macro_translations.push(json!({
+            "macro": format!("ST_MACRO_{index}"),
+            "set": set,
+            "code": code,
+            "candidates": candidates,
+            "chosen": chosen,
+        }))
+    });
+}
+
+/// Record the generated files just moved into place.
+pub(crate) fn files(paths: &[PathBuf]) -> Result<()> {
+    let files = hashes(paths)?;
+    update(|report| report.files = files);
+    Ok(())
+}
+
+pub(crate) fn firmware(firmware: &Firmware) {
+    update(|report| {
+        report.firmware = Some(json!({
+            "path": firmware.path.display().to_string(),
+            "size": firmware.size,
+            "sha256": firmware.sha256,
+        }))
+    });
+}
+
+pub(crate) fn commit(id: Option<git2::Oid>) {
+    update(|report| report.commit = id.map(|id| id.to_string()));
+}
+
+fn hashes(paths: &[PathBuf]) -> Result<Vec<Value>> {
+    paths
+        .iter()
+        .map(|path| {
+            Ok(json!({
+                "path": path.display().to_string(),
+                "sha256": checksum::sha256_file(path)?,
+            }))
+        })
+        .collect()
+}
+
+/// Write everything recorded so far to `path`, with whether the run succeeded.
+pub(crate) fn write(path: &str, error: Option<&anyhow::Error>) -> Result<()> {
+    let report = report()
+        .lock()
+        .map_err(|_| anyhow!("Report lock poisoned."))?;
+    let contents = json!({
+        "status": if error.is_some() { "failed" } else { "ok" },
+        "error": error.map(|error| format!("{error:#}")),
+        "input": report.input,
+        "stages": report.stages,
+        "macro_translations": report.macro_translations,
+        "files": report.files,
+        "firmware": report.firmware,
+        "commit": report.commit,
+    });
+    fs::write(path, serde_json::to_string_pretty(&contents)?)
+        .map_err(|error| anyhow!("Can't write '{path}': {error}"))
+}
@@ -0,0 +1,129 @@
+use crate::{
+    args::Args,
+    config::Config,
+    layout::Layout,
+    logging::{info, progress},
+    oryx,
+    source::{OryxExport, Source},
+    time_stamp, zip,
+};
+use anyhow::{anyhow, Result};
+use std::{
+    fs,
+    io::{self, Write},
+};
+
+// How many recent downloads --pick lists.
+const PICK_COUNT: usize = 10;
+
+/// Choose the input to run the pipeline on: the .zip file, folder or keymap.c given by --input, the layout given
+/// by --fetch freshly downloaded from Oryx, the most recent download of the layout given by --revision, one picked
+/// from a list with --pick, or else the most recent download.
+pub(crate) fn export(config: &Config, args: &Args) -> Result<String> {
+    if let Some(path) = &args.input {
+        return by_path(path);
+    }
+    if let Some(revision) = &args.fetch {
+        let (layout_id, revision_id) = oryx::layout_and_revision(revision);
+        return oryx::fetch(&oryx::Ureq, config, layout_id, revision_id);
+    }
+    if let Some(revision) = &args.revision {
+        return by_revision(revision);
+    }
+    if args.pick {
+        return pick();
+    }
+
+    // Find the most recent downloaded file with prefix "moonlander_" and extension ".zip".
+    progress!("Locating most recent moonlander_* source code .zip file...");
+    let zip = zip::find_most_recent_download()?;
+    info!("found '{zip}'.");
+    Ok(zip)
+}
+
+/// An explicit .zip file, folder or keymap.c, anywhere. Its absolute path stands in for a download's file name.
+fn by_path(path: &str) -> Result<String> {
+    let path = fs::canonicalize(path).map_err(|error| anyhow!("Can't open '{path}': {error}"))?;
+    let input = path.to_string_lossy().to_string();
+    match Source::of(&input)? {
+        Source::Zip if !zip::is_valid(&path) => {
+            return Err(anyhow!("'{input}' isn't a readable .zip file."))
+        }
+        Source::Zip => {}
+        Source::Folder(_) => {
+            OryxExport::read(&input)?;
+        }
+    }
+    info!("Using '{input}'.");
+    Ok(input)
+}
+
+/// The most recent download whose FIRMWARE_VERSION matches "<layout id>" or "<layout id>/<revision id>".
+fn by_revision(revision: &str) -> Result<String> {
+    progress!("Locating most recent download of Oryx layout '{revision}'...");
+    let (layout_id, revision_id) = match revision.split_once('/') {
+        Some((layout_id, revision_id)) => (layout_id, Some(revision_id)),
+        None => (revision, None),
+    };
+    for (zip, _) in zip::find_downloads()? {
+        // Skip downloads which aren't Oryx exports rover can read.
+        let Ok(layout) = Layout::read(&zip) else {
+            continue;
+        };
+        let Some((layout, revision)) = layout.revision() else {
+            continue;
+        };
+        if layout == layout_id && revision_id.is_none_or(|revision_id| revision == revision_id) {
+            info!("found '{zip}'.");
+            return Ok(zip);
+        }
+    }
+    info!("none found.");
+    Err(anyhow!("No download of Oryx layout '{revision}'."))
+}
+
+/// List the most recent downloads with their time stamps, layout titles and revisions, and read a choice.
+fn pick() -> Result<String> {
+    let downloads = zip::find_downloads()?
+        .into_iter()
+        .take(PICK_COUNT)
+        .collect::<Vec<_>>();
+    if downloads.is_empty() {
+        return Err(anyhow!("No .zip file found."));
+    }
+    println!("Recent moonlander_* source code .zip files:");
+    for (i, (zip, modified)) in downloads.iter().enumerate() {
+        let title = zip::title(zip).ok().flatten().unwrap_or_default();
+        let revision = Layout::read(zip)
+            .ok()
+            .and_then(|layout| {
+                layout
+                    .revision()
+                    .map(|(layout, revision)| format!("{layout}/{revision}"))
+            })
+            .unwrap_or_else(|| "unknown revision".to_string());
+        // The list is part of the prompt, so it's shown whatever the verbosity.
+        println!(
+            "{:>3}. {}  {title} ({revision})  '{zip}'",
+            i + 1,
+            time_stamp::format(*modified)
+        );
+    }
+    print!("Which one? [1] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+    let choice = if answer.is_empty() {
+        1
+    } else {
+        answer
+            .parse::<usize>()
+            .map_err(|_| anyhow!("'{answer}' isn't a number."))?
+    };
+    downloads
+        .into_iter()
+        .nth(choice.wrapping_sub(1))
+        .map(|(zip, _)| zip)
+        .ok_or_else(|| anyhow!("There's no download {choice}."))
+}
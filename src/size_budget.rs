@@ -0,0 +1,139 @@
+use crate::{
+    command,
+    config::Config,
+    firmware::Firmware,
+    logging::{info, warning},
+};
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::fs;
+
+// Guesses at the compiled sizes of generated content, for a rough idea of what to trim when flash runs short. They
+// aren't measured: SEND_STRING encodes each tap as three bytes and each delay as four, and the code each case, tap
+// dance and combo adds on top of its data is a ballpark figure.
+const TAP_BYTES: usize = 3;
+const MODIFIER_BYTES: usize = 6;
+const DELAY_BYTES: usize = 4;
+const MACRO_CASE_BYTES: usize = 16;
+const TAP_DANCE_BYTES: usize = 160;
+const TAP_DANCE_ACTION_BYTES: usize = 8;
+const COMBO_BYTES: usize = 8;
+const COMBO_KEY_BYTES: usize = 2;
+
+/// Compare the flash the firmware uses against the keyboard's capacity, warning when within the configured
+/// margin and failing when over. In both cases, show a rough guess at which generated content contributes most.
+pub(crate) fn check(
+    config: &Config,
+    firmware: &Firmware,
+    build_output: &command::Output,
+) -> Result<()> {
+    let Some(capacity) = config.profile().flash_capacity else {
+        info!(
+            "Flash capacity of '{}' unknown; skipping size check.",
+            config.keyboard
+        );
+        return Ok(());
+    };
+    let used = size_tool_usage(build_output)?.unwrap_or(firmware.size);
+    let percent = used * 100 / capacity;
+    info!("Firmware uses {used} of {capacity} bytes of flash ({percent}%).");
+    if used > capacity {
+        print_heuristic_breakdown(config);
+        return Err(anyhow!(
+            "Firmware is {} bytes over the {capacity} bytes of flash of '{}'.",
+            used - capacity,
+            config.keyboard
+        ));
+    }
+    if (capacity - used) * 100 < capacity * config.flash_margin {
+        warning!(
+            "Warning: firmware is within {}% of the flash capacity of '{}'.",
+            config.flash_margin,
+            config.keyboard
+        );
+        print_heuristic_breakdown(config);
+    }
+    Ok(())
+}
+
+/// text + data from the last avr-size/arm-none-eabi-size table the QMK build printed, e.g.
+/// "  60580     104   29396   90080   15fe0 moonlander_chrispetkau.elf".
+fn size_tool_usage(build_output: &command::Output) -> Result<Option<u64>> {
+    let row = Regex::new(
+        r"^[[:space:]]*(\d+)[[:space:]]+(\d+)[[:space:]]+(\d+)[[:space:]]+(\d+)[[:space:]]+[[:xdigit:]]+[[:space:]]+\S+$",
+    )?;
+    Ok(build_output
+        .stdout
+        .iter()
+        .chain(&build_output.stderr)
+        .filter_map(|line| row.captures(line))
+        .next_back()
+        .map(|captures| {
+            captures[1].parse::<u64>().unwrap_or_default()
+                + captures[2].parse::<u64>().unwrap_or_default()
+        }))
+}
+
+/// Print a guess at the bytes contributed by each kind of generated content, largest first. It's only a hint of
+/// what to trim, so failing to produce it doesn't fail the build.
+fn print_heuristic_breakdown(config: &Config) {
+    if let Err(error) = heuristic_breakdown(config) {
+        warning!("Couldn't guess what generated content uses flash: {error}");
+    }
+}
+
+fn heuristic_breakdown(config: &Config) -> Result<()> {
+    let export_folder = config.export_folder();
+    let read =
+        |file_name: &str| fs::read_to_string(export_folder.join(file_name)).unwrap_or_default();
+    let keymap_c = read("keymap.c");
+    let macros = read("petkau_macros.inl") + &keymap_c;
+    let tap_dances = read("petkau_tap_dance.inl");
+
+    let count = |text: &str, pattern: &str| -> Result<usize> {
+        Ok(Regex::new(pattern)?.find_iter(text).count())
+    };
+    let mut contributions = vec![
+        (
+            "macros",
+            count(&macros, r"SS_TAP\(")? * TAP_BYTES
+                + count(&macros, r"SS_[LR](?:SFT|CTL|ALT|GUI)\(")? * MODIFIER_BYTES
+                + count(&macros, r"SS_DELAY\(")? * DELAY_BYTES
+                + count(&macros, r"case (?:PETKAU_MACRO_\w+|ST_MACRO_\d+):")? * MACRO_CASE_BYTES,
+        ),
+        (
+            "tap dances",
+            count(&tap_dances, r"void dance_\d+_finished")? * TAP_DANCE_BYTES
+                + count(
+                    &tap_dances,
+                    r"(?:register_code16|unregister_code16|tap_code16)\(",
+                )? * TAP_DANCE_ACTION_BYTES,
+        ),
+        (
+            "combos",
+            count(&keymap_c, r"COMBO\(")? * COMBO_BYTES
+                + count_combo_keys(&keymap_c)? * COMBO_KEY_BYTES,
+        ),
+    ];
+    contributions.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+    info!("Rough guess (from counting generated code, not measured) of flash used by generated content:");
+    for (name, bytes) in contributions {
+        info!("\t{name}: ~{bytes} bytes");
+    }
+    Ok(())
+}
+
+/// Keys across every "const uint16_t PROGMEM combo#[] = { KC_A, KC_B, COMBO_END};".
+fn count_combo_keys(keymap_c: &str) -> Result<usize> {
+    Ok(
+        Regex::new(r"combo\d+\[\][[:space:]]*=[[:space:]]*\{([^}]*)\}")?
+            .captures_iter(keymap_c)
+            .map(|captures| {
+                captures[1]
+                    .split(',')
+                    .filter(|key| !key.trim().is_empty())
+                    .count()
+            })
+            .sum(),
+    )
+}
@@ -0,0 +1,101 @@
+use crate::zip::{self, EXPORT_FILES};
+use anyhow::{anyhow, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The files of an Oryx source export, read into memory.
+pub(crate) struct OryxExport {
+    /// The input it was read from: a download's file name or a full path.
+    pub(crate) name: String,
+    pub(crate) keymap_c: String,
+    pub(crate) config_h: String,
+    pub(crate) rules_mk: String,
+    /// Files of a .zip file which rover doesn't use, e.g. "keymap.json".
+    pub(crate) extra_files: Vec<String>,
+    /// config.h or rules.mk when missing from beside a keymap.c, and so read as empty.
+    pub(crate) missing_files: Vec<&'static str>,
+}
+
+impl OryxExport {
+    /// Read the keymap.c, config.h and rules.mk of a .zip file, extracted Oryx source folder or lone keymap.c.
+    pub(crate) fn read(input: &str) -> Result<Self> {
+        let Source::Folder(folder) = Source::of(input)? else {
+            return zip::read(input);
+        };
+        let mut missing_files = Vec::new();
+        let mut read = |file_name: &'static str| -> Result<String> {
+            let path = folder.join(file_name);
+            if file_name != "keymap.c" && !path.exists() {
+                missing_files.push(file_name);
+                return Ok(String::new());
+            }
+            fs::read_to_string(&path)
+                .map_err(|error| anyhow!("Can't read '{}': {error}", path.display()))
+        };
+        Ok(Self {
+            name: input.to_string(),
+            keymap_c: read("keymap.c")?,
+            config_h: read("config.h")?,
+            rules_mk: read("rules.mk")?,
+            extra_files: Vec::new(),
+            missing_files,
+        })
+    }
+}
+
+/// Where an input's files live: in a downloaded .zip file, or in a folder, i.e. an extracted Oryx source folder or
+/// the one holding a lone keymap.c, which may have config.h and rules.mk beside it.
+pub(crate) enum Source {
+    Zip,
+    Folder(PathBuf),
+}
+
+impl Source {
+    /// Classify an input, either a download's file name or a full path.
+    pub(crate) fn of(input: &str) -> Result<Self> {
+        let path = zip::path(input);
+        if path.is_dir() {
+            return Ok(Self::Folder(keymap_folder(&path)?));
+        }
+        if path
+            .file_name()
+            .is_some_and(|file_name| file_name == "keymap.c")
+        {
+            let folder = path
+                .parent()
+                .ok_or_else(|| anyhow!("'{}' has no folder.", path.display()))?;
+            return Ok(Self::Folder(folder.to_path_buf()));
+        }
+        Ok(Self::Zip)
+    }
+}
+
+/// The folder holding keymap.c: the given one, or the moonlander_* folder a .zip file extracts to within it.
+fn keymap_folder(path: &Path) -> Result<PathBuf> {
+    if path.join("keymap.c").is_file() {
+        return Ok(path.to_path_buf());
+    }
+    fs::read_dir(path)?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .find(|folder| {
+            folder
+                .file_name()
+                .is_some_and(|file_name| file_name.to_string_lossy().starts_with("moonlander_"))
+                && folder.join("keymap.c").is_file()
+        })
+        .ok_or_else(|| anyhow!("'{}' has no keymap.c.", path.display()))
+}
+
+/// The files on disk which make up the input, for archiving.
+pub(crate) fn files(input: &str) -> Result<Vec<PathBuf>> {
+    match Source::of(input)? {
+        Source::Zip => Ok(vec![zip::path(input)]),
+        Source::Folder(folder) => Ok(EXPORT_FILES
+            .iter()
+            .map(|file_name| folder.join(file_name))
+            .filter(|path| path.is_file())
+            .collect()),
+    }
+}
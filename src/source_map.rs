@@ -0,0 +1,110 @@
+use crate::staging;
+use anyhow::{anyhow, Result};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    io::{self, Write},
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+
+/// The rover stage which generated a line of output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Stage {
+    /// Copied unchanged from the Oryx export.
+    OryxPassthrough,
+    MacroTranslation,
+    TapDance,
+    /// Added by rover to hook in the hand-maintained petkau_*.inl files and settings.
+    Override,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Stage::OryxPassthrough => "Oryx passthrough",
+            Stage::MacroTranslation => "macro translation",
+            Stage::TapDance => "tap dance",
+            Stage::Override => "override",
+        })
+    }
+}
+
+/// Where a generated line came from: the stage, and the originating config entry or Oryx key position.
+#[derive(Clone, Debug)]
+pub(crate) struct Origin {
+    pub(crate) stage: Stage,
+    pub(crate) detail: String,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.stage, self.detail)
+    }
+}
+
+/// Origin of each line of each generated file, keyed by file name.
+fn source_map() -> &'static Mutex<BTreeMap<String, Vec<Origin>>> {
+    static MAP: OnceLock<Mutex<BTreeMap<String, Vec<Origin>>>> = OnceLock::new();
+    MAP.get_or_init(Default::default)
+}
+
+/// The origin of the given (1-based) line of the generated file, if rover generated it.
+pub(crate) fn lookup(file_name: &str, line: usize) -> Option<Origin> {
+    source_map()
+        .lock()
+        .ok()?
+        .get(file_name)?
+        .get(line.checked_sub(1)?)
+        .cloned()
+}
+
+/// A generated file which records the current origin of every line written to it. It's staged, replacing the file
+/// in the folder only on staging::commit().
+pub(crate) struct TrackedFile {
+    file: fs::File,
+    file_name: String,
+    origin: Origin,
+}
+
+impl TrackedFile {
+    pub(crate) fn create(folder: &Path, file_name: &str) -> Result<Self> {
+        let file = staging::create(&folder.join(file_name))?;
+        source_map()
+            .lock()
+            .map_err(|_| anyhow!("Source map lock poisoned."))?
+            .insert(file_name.to_string(), Vec::new());
+        Ok(Self {
+            file,
+            file_name: file_name.to_string(),
+            origin: Origin {
+                stage: Stage::OryxPassthrough,
+                detail: String::new(),
+            },
+        })
+    }
+
+    /// Attribute subsequently written lines to the given stage and detail.
+    pub(crate) fn set_origin(&mut self, stage: Stage, detail: impl Into<String>) {
+        self.origin = Origin {
+            stage,
+            detail: detail.into(),
+        };
+    }
+}
+
+impl Write for TrackedFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.file.write(buf)?;
+        let lines = buf[..written].iter().filter(|&&b| b == b'\n').count();
+        if let Ok(mut map) = source_map().lock() {
+            let origins = map.entry(self.file_name.clone()).or_default();
+            origins.extend(std::iter::repeat_n(self.origin.clone(), lines));
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -0,0 +1,102 @@
+use crate::logging::{info, progress};
+use anyhow::{anyhow, Result};
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+// Suffix of a generated file while it's being written, and of the file it replaces while the set is swapped in.
+const STAGED_SUFFIX: &str = ".rover-new";
+const BACKUP_SUFFIX: &str = ".rover-old";
+
+/// Paths of the generated files written since the last commit or abort.
+fn staged() -> &'static Mutex<Vec<PathBuf>> {
+    static STAGED: OnceLock<Mutex<Vec<PathBuf>>> = OnceLock::new();
+    STAGED.get_or_init(Default::default)
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Create a file which replaces the one at `path` only once every staged file is committed.
+pub(crate) fn create(path: &Path) -> Result<fs::File> {
+    let file = fs::File::create(with_suffix(path, STAGED_SUFFIX))?;
+    let mut staged = staged()
+        .lock()
+        .map_err(|_| anyhow!("Staged files lock poisoned."))?;
+    if !staged.iter().any(|staged| staged == path) {
+        staged.push(path.to_path_buf());
+    }
+    Ok(file)
+}
+
+/// The paths of the files staged so far.
+pub(crate) fn paths() -> Vec<PathBuf> {
+    staged()
+        .lock()
+        .map_or_else(|_| Vec::new(), |staged| staged.clone())
+}
+
+/// Move every staged file into place. Should any move fail, put back every file as it was before.
+pub(crate) fn commit() -> Result<()> {
+    let paths = std::mem::take(
+        &mut *staged()
+            .lock()
+            .map_err(|_| anyhow!("Staged files lock poisoned."))?,
+    );
+    progress!("Replacing generated files...");
+    let mut backed_up = Vec::new();
+    let mut placed = Vec::new();
+    let mut swap = || -> Result<()> {
+        for path in &paths {
+            if path.exists() {
+                fs::rename(path, with_suffix(path, BACKUP_SUFFIX))?;
+                backed_up.push(path);
+            }
+        }
+        for path in &paths {
+            fs::rename(with_suffix(path, STAGED_SUFFIX), path)?;
+            placed.push(path);
+        }
+        Ok(())
+    };
+    match swap() {
+        Ok(()) => {
+            for path in backed_up {
+                fs::remove_file(with_suffix(path, BACKUP_SUFFIX))?;
+            }
+            info!("done.");
+            Ok(())
+        }
+        Err(error) => {
+            info!("failed. Restoring the previous files.");
+            for path in placed {
+                let _ = fs::remove_file(path);
+            }
+            for path in backed_up {
+                fs::rename(with_suffix(path, BACKUP_SUFFIX), path)?;
+            }
+            for path in &paths {
+                let _ = fs::remove_file(with_suffix(path, STAGED_SUFFIX));
+            }
+            Err(error)
+        }
+    }
+}
+
+/// Delete every staged file, leaving the files they would have replaced untouched.
+pub(crate) fn abort() {
+    let Ok(mut staged) = staged().lock() else {
+        return;
+    };
+    progress!("Discarding generated files...");
+    for path in staged.drain(..) {
+        let _ = fs::remove_file(with_suffix(&path, STAGED_SUFFIX));
+    }
+    info!("done.");
+}
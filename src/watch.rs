@@ -0,0 +1,110 @@
+use crate::{
+    args::Args,
+    config::Config,
+    logging::{info, warning},
+    pipeline,
+    source::OryxExport,
+    zip,
+};
+use anyhow::{anyhow, Result};
+use guard::continue_unless;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+// How long a download's size must hold still before it's considered complete.
+const STABLE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Give up on a download which is still changing after this long.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Watch the import folder, generating and compiling each new moonlander_* .zip file as it lands, then asking
+/// before flashing it.
+pub(crate) fn run(config: &Config, args: &Args) -> Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(&config.import_folder, RecursiveMode::NonRecursive)?;
+    info!(
+        "Watching '{}' for moonlander_* .zip files. Press Ctrl+C to stop.",
+        config.import_folder.display()
+    );
+
+    // Modification time of each download already run, since one download raises many events.
+    let mut processed = HashMap::new();
+    for event in receiver {
+        let event = event?;
+        continue_unless!(matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_)
+        ));
+        for path in event.paths {
+            let Some(file_name) = path.file_name().and_then(|file_name| file_name.to_str()) else {
+                continue;
+            };
+            continue_unless!(zip::is_download(file_name));
+            let modified = match wait_for_download(file_name) {
+                Ok(modified) => modified,
+                Err(error) => {
+                    warning!("Skipping '{file_name}': {error}");
+                    continue;
+                }
+            };
+            continue_unless!(processed.get(file_name) != Some(&modified));
+            processed.insert(file_name.to_string(), modified);
+
+            info!("New download '{file_name}'.");
+            if let Err(error) = run_once(config, file_name, args) {
+                warning!("Failed to integrate '{file_name}': {error:#}");
+            }
+            info!("Watching '{}' again.", config.import_folder.display());
+        }
+    }
+    Ok(())
+}
+
+/// Wait until the download's size stops changing and it reads as a valid .zip file, returning its modification
+/// time.
+fn wait_for_download(file_name: &str) -> Result<SystemTime> {
+    let path = zip::path(file_name);
+    let deadline = Instant::now() + DOWNLOAD_TIMEOUT;
+    let mut size = fs::metadata(&path)?.len();
+    loop {
+        thread::sleep(STABLE_INTERVAL);
+        let metadata = fs::metadata(&path)?;
+        if metadata.len() == size && size > 0 && zip::is_valid(&path) {
+            return Ok(metadata.modified()?);
+        }
+        if Instant::now() >= deadline {
+            return Err(anyhow!(
+                "still incomplete after {} seconds.",
+                DOWNLOAD_TIMEOUT.as_secs()
+            ));
+        }
+        size = metadata.len();
+    }
+}
+
+fn run_once(config: &Config, zip: &str, args: &Args) -> Result<()> {
+    let export = OryxExport::read(zip)?;
+    pipeline::generate(config, &export, args.force)?;
+    let firmware = pipeline::build(config)?;
+    print!(
+        "Flash '{}' to the keyboard? [y/N] ",
+        firmware.path.display()
+    );
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    if answer.trim().eq_ignore_ascii_case("y") {
+        pipeline::deliver(config, &export, &firmware, args.tag)
+    } else {
+        info!("Not flashed. The generated files are left uncommitted.");
+        Ok(())
+    }
+}
@@ -1,13 +1,67 @@
+use crate::{
+    config::{self, Config},
+    source::OryxExport,
+};
 use anyhow::{anyhow, Result};
 use guard::continue_unless;
-use std::{fs, io, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
 
-// Import folder is hard-coded to "C:\Users\Chris Petkau\Downloads".
-const IMPORT_FOLDER: &str = "C:/Users/Chris Petkau/Downloads";
+// The files of an Oryx source export rover reads.
+pub(crate) const EXPORT_FILES: [&str; 3] = ["keymap.c", "config.h", "rules.mk"];
+
+/// The configured folder downloads land in. Inputs are only read once the config has loaded, so the default is a
+/// formality.
+fn import_folder() -> PathBuf {
+    config::get().map_or_else(
+        |_| Config::default().import_folder,
+        |config| config.import_folder.clone(),
+    )
+}
+
+/// Full path of a downloaded file.
+pub(crate) fn path(zip: &str) -> PathBuf {
+    import_folder().join(zip)
+}
+
+/// Whether the file name has prefix "moonlander_" and extension ".zip".
+pub(crate) fn is_download(file_name: &str) -> bool {
+    file_name.starts_with("moonlander_") && file_name.ends_with(".zip")
+}
+
+/// Whether the file is a readable .zip file, i.e. not still being written.
+pub(crate) fn is_valid(path: &Path) -> bool {
+    fs::File::open(path)
+        .ok()
+        .and_then(|file| zip::ZipArchive::new(file).ok())
+        .is_some()
+}
 
 /// Find the most recent downloaded file with prefix "moonlander_" and extension ".zip".
 pub(crate) fn find_most_recent_download() -> Result<String> {
-    Ok(fs::read_dir(IMPORT_FOLDER)?
+    find_most_recent_downloads(1)?
+        .pop()
+        .ok_or_else(|| anyhow!("No .zip file found."))
+}
+
+/// Find up to `count` downloaded files with prefix "moonlander_" and extension ".zip", most recent first.
+pub(crate) fn find_most_recent_downloads(count: usize) -> Result<Vec<String>> {
+    Ok(find_downloads()?
+        .into_iter()
+        .take(count)
+        .map(|(file_name, _)| file_name)
+        .collect())
+}
+
+/// Find every downloaded file with prefix "moonlander_" and extension ".zip" with its modification time, most
+/// recent first.
+pub(crate) fn find_downloads() -> Result<Vec<(String, SystemTime)>> {
+    let mut downloads = fs::read_dir(import_folder())?
         .filter_map(|entry| {
             let entry = entry.ok()?;
             let file_name = entry.file_name().into_string().ok()?;
@@ -19,44 +73,176 @@ pub(crate) fn find_most_recent_download() -> Result<String> {
             }
         })
         .filter_map(|(file_name, time_stamp)| {
-            if file_name.starts_with("moonlander_") && file_name.ends_with(".zip") {
+            if is_download(&file_name) {
                 Some((file_name, time_stamp))
             } else {
                 None
             }
         })
-        .max_by_key(|(_, time_stamp)| *time_stamp)
-        .ok_or_else(|| anyhow!("No .zip file found."))?
-        .0)
-}
-
-/// Extract files and put them in the "temp" folder.
-pub(crate) fn extract_files_to_temp(zip: &str) -> Result<()> {
-    let mut zip = zip::ZipArchive::new(fs::File::open(Path::new(IMPORT_FOLDER).join(&zip))?)?;
-    for i in 0..zip.len() {
-        let mut file = zip.by_index(i)?;
-        let file_name = file.name();
-        continue_unless!(file_name.starts_with("moonlander_"));
-        continue_unless!(!file_name.ends_with('/'));
-        let outpath = match file.enclosed_name() {
-            Some(path) => {
-                let path = Path::new(
-                    path.components()
-                        .last()
-                        .ok_or_else(|| anyhow!("Empty filename in .zip file."))?
-                        .as_os_str(),
-                );
-                Path::new(crate::temp_folder::NAME).join(path)
-            }
-            None => continue,
-        };
-        println!(
-            "Entry {} is a file. Extracting \"{}\" ({} bytes)",
-            i,
-            outpath.display(),
-            file.size()
+        .collect::<Vec<_>>();
+    downloads.sort_by_key(|(_, time_stamp)| std::cmp::Reverse(*time_stamp));
+    Ok(downloads)
+}
+
+/// The layout title Oryx put in the name of the export's folder, e.g. "my-layout" from
+/// "moonlander_my-layout_source/keymap.c".
+pub(crate) fn title(zip: &str) -> Result<Option<String>> {
+    let zip = zip::ZipArchive::new(fs::File::open(path(zip))?)?;
+    let title = zip
+        .file_names()
+        .filter_map(|name| name.split('/').next())
+        .find_map(|folder| folder.strip_prefix("moonlander_"))
+        .map(|title| title.trim_end_matches("_source").to_string());
+    Ok(title)
+}
+
+/// Read the contents of keymap.c, config.h and rules.mk into memory, noting any other files.
+pub(crate) fn read(zip: &str) -> Result<OryxExport> {
+    let mut archive = open(zip)?;
+    let mut files = BTreeMap::new();
+    let mut extra_files = Vec::new();
+    for (i, path) in entries(&mut archive, zip)? {
+        let file_name = path_key(&path);
+        // Match as entries() does, so "Keymap.c" is read as keymap.c.
+        let key = file_name.to_lowercase();
+        if !EXPORT_FILES.contains(&key.as_str()) {
+            extra_files.push(file_name);
+            continue;
+        }
+        let mut contents = String::new();
+        archive.by_index(i)?.read_to_string(&mut contents)?;
+        files.insert(key, contents);
+    }
+    // entries() has already checked every one of EXPORT_FILES is present.
+    let mut take = |file_name: &str| files.remove(file_name).unwrap_or_default();
+    Ok(OryxExport {
+        name: zip.to_string(),
+        keymap_c: take("keymap.c"),
+        config_h: take("config.h"),
+        rules_mk: take("rules.mk"),
+        extra_files,
+        missing_files: Vec::new(),
+    })
+}
+
+fn open(zip: &str) -> Result<zip::ZipArchive<fs::File>> {
+    Ok(zip::ZipArchive::new(fs::File::open(path(zip))?)?)
+}
+
+/// The index and path of each file in the .zip file, relative to the one folder Oryx puts everything in (e.g.
+/// "moonlander_my-layout_source"). Fails on paths escaping the export, on two files extracting to the same place
+/// and when any of keymap.c, config.h and rules.mk is missing.
+fn entries(archive: &mut zip::ZipArchive<fs::File>, zip: &str) -> Result<Vec<(usize, PathBuf)>> {
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+        continue_unless!(file.is_file());
+        let path = file
+            .enclosed_name()
+            .ok_or_else(|| anyhow!("'{zip}' has a file outside its folder: '{}'.", file.name()))?
+            .to_path_buf();
+        entries.push((i, path));
+    }
+
+    // Strip the export's folder when every file is in it.
+    let root = entries
+        .first()
+        .and_then(|(_, path)| path.components().next())
+        .map(|root| PathBuf::from(root.as_os_str()))
+        .filter(|root| {
+            entries
+                .iter()
+                .all(|(_, path)| path.starts_with(root) && path != root)
+        });
+    if let Some(root) = root {
+        for (_, path) in &mut entries {
+            *path = path.strip_prefix(&root)?.to_path_buf();
+        }
+    }
+
+    // Compare case-insensitively, since Windows file names are.
+    let mut extracted_to = HashMap::new();
+    for (i, path) in &entries {
+        if let Some(other) = extracted_to.insert(path_key(path).to_lowercase(), *i) {
+            return Err(anyhow!(
+                "'{zip}' entries {other} and {i} would both extract to '{}'.",
+                path.display()
+            ));
+        }
+    }
+    let missing = EXPORT_FILES
+        .iter()
+        .filter(|file_name| !extracted_to.contains_key(**file_name))
+        .copied()
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(anyhow!("'{zip}' has no {}.", missing.join(", ")));
+    }
+    Ok(entries)
+}
+
+/// The path with "/" separators, as .zip files have them.
+fn path_key(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Write a .zip file of the given files into a folder of its own, returning its path.
+    fn write_zip(test: &str, files: &[(&str, &str)]) -> String {
+        let folder = std::env::temp_dir().join(format!("rover-zip-{test}"));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).unwrap();
+        let path = folder.join("moonlander_my-layout_source.zip");
+        let mut writer = ::zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        for (name, contents) in files {
+            writer
+                .start_file(
+                    format!("moonlander_my-layout_source/{name}"),
+                    ::zip::write::FileOptions::default(),
+                )
+                .unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn reads_export_files_whatever_their_case() {
+        let zip = write_zip(
+            "case",
+            &[
+                ("Keymap.c", "keymap"),
+                ("CONFIG.H", "config"),
+                ("rules.mk", "rules"),
+                ("README.md", "readme"),
+            ],
+        );
+        let export = read(&zip).unwrap();
+        assert_eq!(export.keymap_c, "keymap");
+        assert_eq!(export.config_h, "config");
+        assert_eq!(export.rules_mk, "rules");
+        assert_eq!(export.extra_files, ["README.md"]);
+    }
+
+    #[test]
+    fn rejects_files_differing_only_in_case() {
+        let zip = write_zip(
+            "collision",
+            &[
+                ("keymap.c", "keymap"),
+                ("Keymap.c", "keymap"),
+                ("config.h", "config"),
+                ("rules.mk", "rules"),
+            ],
         );
-        io::copy(&mut file, &mut fs::File::create(&outpath)?)?;
+        assert!(read(&zip).is_err());
     }
-    Ok(())
 }
@@ -0,0 +1,10 @@
+/*
+  Set any config.h overrides for your specific keymap here.
+*/
+#define ORYX_CONFIGURATOR
+#undef TAPPING_TERM
+#define TAPPING_TERM 180
+
+#define USB_SUSPEND_WAKEUP_DELAY 0
+#define FIRMWARE_VERSION u8"aBcDe/qrs34"
+#define RGB_MATRIX_STARTUP_SPD 60